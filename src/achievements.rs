@@ -0,0 +1,141 @@
+//
+
+use std::{collections::HashSet, env, fmt, fs, io, path::PathBuf};
+
+// Name of the file, in the home directory of the user, where unlocked achievements are stored.
+const STORAGE_FILE: &str = ".rmines_achievements";
+
+// Summary of a won game, used to decide which achievements it unlocks.
+pub struct GameSummary {
+    pub rows: usize,
+    pub cols: usize,
+    pub mines: usize,
+    pub seconds: u64,
+    pub used_flags: bool,
+}
+
+impl GameSummary {
+    // The classic expert board has 16x30 cells and 99 mines.
+    fn is_expert(&self) -> bool {
+        ((self.rows, self.cols) == (16, 30) || (self.rows, self.cols) == (30, 16)) && self.mines >= 99
+    }
+}
+
+pub struct Achievement {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    unlocked_by: fn(&GameSummary) -> bool,
+}
+
+pub static REGISTRY: [Achievement; 4] = [
+    Achievement {
+        id: "expert-win",
+        name: "Expert",
+        description: "win a game on an expert board (16x30, 99 mines)",
+        unlocked_by: |game| game.is_expert(),
+    },
+    Achievement {
+        id: "expert-sub-100",
+        name: "Speed demon",
+        description: "win a game on an expert board in less than 100 seconds",
+        unlocked_by: |game| game.is_expert() && game.seconds < 100,
+    },
+    Achievement {
+        id: "no-flags",
+        name: "Look, no flags!",
+        description: "win a game without placing a single flag",
+        unlocked_by: |game| !game.used_flags,
+    },
+    Achievement {
+        id: "big-board",
+        name: "Marathon",
+        description: "clear a board of at least 50x50 cells",
+        unlocked_by: |game| game.rows >= 50 && game.cols >= 50,
+    },
+];
+
+pub struct Achievements {
+    unlocked: HashSet<&'static str>,
+    path: Option<PathBuf>,
+}
+
+impl fmt::Display for Achievements {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for achievement in &REGISTRY {
+            let status = if self.unlocked.contains(achievement.id) { "unlocked" } else { "locked" };
+            writeln!(f, "- [{status:>8}] {}: {}.", achievement.name, achievement.description)?;
+        }
+        Ok(())
+    }
+}
+
+impl Achievements {
+
+    // Load the achievements unlocked in previous sessions. A missing or unreadable
+    // file just means that nothing has been unlocked yet.
+    pub fn load() -> Self {
+
+        let path = env::var_os("HOME").map(|home| PathBuf::from(home).join(STORAGE_FILE));
+        let stored = path.as_ref().and_then(|path| fs::read_to_string(path).ok()).unwrap_or_default();
+
+        let unlocked = REGISTRY
+            .iter()
+            .filter(|achievement| stored.lines().any(|line| line.trim() == achievement.id))
+            .map(|achievement| achievement.id)
+            .collect();
+
+        Achievements { unlocked, path }
+    }
+
+    // Unlock the achievements earned by `game' and return the ones unlocked for the first time.
+    pub fn evaluate(&mut self, game: &GameSummary) -> io::Result<Vec<&'static Achievement>> {
+
+        let new: Vec<&'static Achievement> = REGISTRY
+            .iter()
+            .filter(|achievement| !self.unlocked.contains(achievement.id) && (achievement.unlocked_by)(game))
+            .collect();
+
+        if !new.is_empty() {
+            self.unlocked.extend(new.iter().map(|achievement| achievement.id));
+            self.save()?;
+        }
+
+        Ok(new)
+    }
+
+    fn save(&self) -> io::Result<()> {
+
+        let Some(path) = &self.path else {
+            return Ok(()) };
+
+        let contents: String = REGISTRY
+            .iter()
+            .filter(|achievement| self.unlocked.contains(achievement.id))
+            .map(|achievement| format!("{}\n", achievement.id))
+            .collect();
+
+        fs::write(path, contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn achievements_are_unlocked_once() {
+        // Without a storage file, achievements are only kept in memory.
+        let mut achievements = Achievements { unlocked: HashSet::new(), path: None };
+        let expert = GameSummary { rows: 30, cols: 16, mines: 99, seconds: 90, used_flags: true };
+
+        let ids: Vec<&str> = achievements.evaluate(&expert).unwrap().iter().map(|a| a.id).collect();
+        assert_eq!(ids, vec!["expert-win", "expert-sub-100"]);
+        assert!(achievements.evaluate(&expert).unwrap().is_empty());
+
+        let no_flags = GameSummary { rows: 9, cols: 9, mines: 10, seconds: 20, used_flags: false };
+        let ids: Vec<&str> = achievements.evaluate(&no_flags).unwrap().iter().map(|a| a.id).collect();
+        assert_eq!(ids, vec!["no-flags"]);
+        assert!(achievements.to_string().contains("[  locked] Marathon"));
+    }
+}
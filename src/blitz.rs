@@ -0,0 +1,68 @@
+//
+
+use std::time::{Duration, SystemTime};
+use crate::game::{Board, BoardError};
+
+// A blitz run: clear as many boards of the same kind as possible before the deadline.
+pub struct Blitz {
+    deadline: SystemTime,
+
+    // Dimensions and mine count of every board in the run.
+    rows: usize,
+    cols: usize,
+    mine_count: usize,
+
+    // Number of boards cleared so far.
+    cleared: usize,
+}
+
+impl Blitz {
+
+    pub fn new(seconds: u64, rows: usize, cols: usize, mine_count: usize) -> Self {
+        Blitz {
+            deadline: SystemTime::now() + Duration::from_secs(seconds),
+            rows,
+            cols,
+            mine_count,
+            cleared: 0,
+        }
+    }
+
+    pub fn get_seconds_left(&self) -> u64 {
+        self.deadline
+            .duration_since(SystemTime::now())
+            .map_or(0, |duration| duration.as_secs())
+    }
+
+    pub fn is_over(&self) -> bool {
+        SystemTime::now() >= self.deadline
+    }
+
+    pub fn get_cleared(&self) -> usize {
+        self.cleared
+    }
+
+    // Count the current board as cleared and roll to the next one.
+    pub fn next_board(&mut self) -> Result<Board, BoardError> {
+        self.cleared += 1;
+        Board::new(self.rows, self.cols, self.mine_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boards_roll_until_the_deadline() {
+        let mut blitz = Blitz::new(60, 9, 9, 10);
+        assert!(!blitz.is_over());
+        assert_eq!(blitz.get_cleared(), 0);
+
+        let board = blitz.next_board().unwrap();
+        assert_eq!((board.get_rows(), board.get_cols(), board.get_mine_count()), (9, 9, 10));
+        assert_eq!(blitz.get_cleared(), 1);
+
+        assert!(Blitz::new(0, 9, 9, 10).is_over());
+    }
+}
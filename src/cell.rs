@@ -0,0 +1,115 @@
+//
+
+use crate::game::PowerUp;
+
+// What is known of a cell, as the game goes on.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CellState {
+    #[default]
+    Hidden,
+    Flagged,
+    // Marked with a question mark, which is not counted as a flag.
+    Questioned,
+    Revealed,
+    // Mined, and hit at the cost of a life.
+    Exploded,
+}
+
+// A cell of the board: what it hides, and what is known of it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cell {
+    pub mined: bool,
+    pub treasure: bool,
+    pub power_up: Option<PowerUp>,
+
+    // Number of mined neighbors, kept up to date as mines are placed and moved.
+    pub adjacent: usize,
+
+    // On liar boards, whether the number of the cell is one more than its count of mined
+    // neighbors (it is one less otherwise, unless that count is 0).
+    pub lies_up: bool,
+
+    pub state: CellState,
+}
+
+impl Cell {
+
+    // Cycle through no mark, a flag and a question mark, and return the new state. Cells
+    // explored or exploded keep their state, and None is returned.
+    pub fn cycle_mark(&mut self) -> Option<CellState> {
+        self.state = match self.state {
+            CellState::Hidden => CellState::Flagged,
+            CellState::Flagged => CellState::Questioned,
+            CellState::Questioned => CellState::Hidden,
+            CellState::Revealed | CellState::Exploded => return None,
+        };
+        Some(self.state)
+    }
+
+    // Flag the cell, unless it was explored or exploded.
+    pub fn flag(&mut self) {
+        if matches!(self.state, CellState::Hidden | CellState::Questioned) {
+            self.state = CellState::Flagged;
+        }
+    }
+
+    // Explore the cell. Any mark on it is removed.
+    pub fn reveal(&mut self) {
+        self.state = CellState::Revealed;
+    }
+
+    pub fn explode(&mut self) {
+        self.state = CellState::Exploded;
+    }
+
+    // Forget everything known of the cell.
+    pub fn reset(&mut self) {
+        self.state = CellState::Hidden;
+    }
+
+    pub fn is_explored(&self) -> bool {
+        self.state == CellState::Revealed
+    }
+
+    pub fn is_flagged(&self) -> bool {
+        self.state == CellState::Flagged
+    }
+
+    pub fn is_exploded(&self) -> bool {
+        self.state == CellState::Exploded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_cycle_through_flag_and_question_mark() {
+        let mut cell = Cell::default();
+        assert_eq!(cell.cycle_mark(), Some(CellState::Flagged));
+        assert_eq!(cell.cycle_mark(), Some(CellState::Questioned));
+        assert_eq!(cell.cycle_mark(), Some(CellState::Hidden));
+
+        cell.reveal();
+        assert_eq!(cell.cycle_mark(), None);
+        assert_eq!(cell.state, CellState::Revealed);
+    }
+
+    #[test]
+    fn flags_are_only_placed_on_unexplored_cells() {
+        let mut cell = Cell { mined: true, ..Cell::default() };
+        cell.explode();
+        cell.flag();
+        assert!(cell.is_exploded());
+
+        let mut cell = Cell { state: CellState::Questioned, ..Cell::default() };
+        cell.flag();
+        assert!(cell.is_flagged());
+
+        cell.reveal();
+        assert!(cell.is_explored() && !cell.is_flagged());
+    }
+}
@@ -0,0 +1,70 @@
+//
+
+use std::time::SystemTime;
+
+// What is counted during a game, apart from the state of the board. Everything starts over with
+// each new game, restart, or board of a blitz run.
+pub struct Counters {
+    pub start_time: SystemTime,
+
+    // Number of moves (explorations, chords, hints, defusals and flags) made so far.
+    pub moves: usize,
+
+    // Whether any cell was flagged.
+    pub used_flags: bool,
+
+    // Number of treasures found, and of power-ups found and not used yet.
+    pub treasures: usize,
+    pub radars: usize,
+    pub defusers: usize,
+
+    // Number of mines placed by the sudden-death modifier.
+    pub added_mines: usize,
+}
+
+impl Default for Counters {
+    fn default() -> Self {
+        Counters {
+            start_time: SystemTime::now(),
+            moves: 0,
+            used_flags: false,
+            treasures: 0,
+            radars: 0,
+            defusers: 0,
+            added_mines: 0,
+        }
+    }
+}
+
+impl Counters {
+
+    // Start counting again, from now.
+    pub fn reset(&mut self) {
+        *self = Counters::default();
+    }
+
+    // Seconds elapsed since the start of the game.
+    pub fn get_seconds(&self) -> u64 {
+        self.start_time.elapsed().map_or(0, |duration| duration.as_secs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn reset_starts_over() {
+        let mut counters = Counters { start_time: SystemTime::now() - Duration::from_secs(90), ..Counters::default() };
+        counters.moves = 12;
+        counters.used_flags = true;
+        counters.radars = 2;
+        counters.added_mines = 3;
+        assert!(counters.get_seconds() >= 90);
+
+        counters.reset();
+        assert_eq!((counters.moves, counters.used_flags, counters.radars, counters.added_mines), (0, false, 0, 0));
+        assert!(counters.get_seconds() < 90);
+    }
+}
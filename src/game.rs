@@ -1,7 +1,10 @@
 // 
 
-use std::{collections::{HashMap, HashSet}, fmt};
-use rand::distributions::{Distribution, Uniform};
+use std::{collections::{HashSet, VecDeque}, fmt};
+use log::debug;
+use crate::cell::{Cell, CellState};
+use crate::render::{AsciiRenderer, Renderer};
+use rand::{rngs::StdRng, seq::{index, IteratorRandom, SliceRandom}, Rng, SeedableRng};
 
 pub type Coord = (usize, usize);
 
@@ -9,61 +12,348 @@ pub type Coord = (usize, usize);
 pub enum BoardError {
     NullArea,
     TooManyMines,
+    // No board that can be solved without guessing was found.
+    NoGuessNotFound,
+}
+
+// Number of boards generated at most when looking for one that can be solved without guessing.
+const NO_GUESS_ATTEMPTS: usize = 1000;
+
+// Number of cells a board can be expanded to at most.
+pub const MAX_EXPANDED_AREA: usize = 1 << 20;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PowerUp {
+    Radar,
+    Defuse,
+}
+
+impl fmt::Display for PowerUp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PowerUp::Radar => write!(f, "radar"),
+            PowerUp::Defuse => write!(f, "defuse"),
+        }
+    }
+}
+
+impl fmt::Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardError::NullArea => write!(f, "cannot create a board with zero rows or columns"),
+            BoardError::TooManyMines => write!(f, "too many mines for such a small board"),
+            BoardError::NoGuessNotFound => write!(f, "could not find a board that can be solved without guessing"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    // Left and right halves mirror each other.
+    Horizontal,
+    // Top and bottom halves mirror each other.
+    Vertical,
+    // The board looks the same when rotated by 180 degrees.
+    Rot180,
+}
+
+// Which cells count as neighbors of a cell, both for its number and for flood filling.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Adjacency {
+    // Every cell within the given distance along rows and columns (1 is the classic rule).
+    Radius(usize),
+    // Only the (up to four) cells sharing a side with the cell.
+    Orthogonal,
+}
+
+// How the rows and columns of the board are labeled, both on screen and in commands.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LabelScheme {
+    // Numbers for both rows and columns, as in `(3, 5)'.
+    Numbers,
+    // Numbers for rows and letters for columns, as in `(3, e)'.
+    LetterColumns,
+    // Letters for rows and numbers for columns, as in `(c, 5)'.
+    LetterRows,
+    // Letters for columns and numbers for rows counted from the bottom, column first, as in `e3'.
+    Chess,
+}
+
+impl LabelScheme {
+
+    // Label of the row `row' (counted from 1 at the top) of a board with `rows' rows.
+    pub fn row_label(self, row: usize, rows: usize) -> String {
+        match self {
+            LabelScheme::Numbers | LabelScheme::LetterColumns => row.to_string(),
+            LabelScheme::LetterRows => letters(row),
+            LabelScheme::Chess => (rows + 1).saturating_sub(row).to_string(),
+        }
+    }
+
+    // Label of the column `col' (counted from 1 at the left).
+    pub fn col_label(self, col: usize) -> String {
+        match self {
+            LabelScheme::Numbers | LabelScheme::LetterRows => col.to_string(),
+            LabelScheme::LetterColumns | LabelScheme::Chess => letters(col),
+        }
+    }
+
+    // Label of the cell at `(row, col)' (counted from 1) of a board with `rows' rows.
+    pub fn cell_label(self, (row, col): Coord, rows: usize) -> String {
+        match self {
+            LabelScheme::Chess => format!("{}{}", self.col_label(col), self.row_label(row, rows)),
+            _ => format!("({}, {})", self.row_label(row, rows), self.col_label(col)),
+        }
+    }
+
+    // Rewrite a cell typed in this scheme, for a board with `rows' rows, as the row and column
+    // numbers (counted from 1 at the top left) separated by a comma. Missing values are left out.
+    // Return None if the labels are not valid.
+    pub fn to_numbers(self, cell: &str, rows: usize) -> Option<String> {
+
+        let convert = |label: &str, to_number: fn(&str) -> Option<usize>| -> Option<String> {
+            if label.is_empty() { Some(String::new()) } else { to_number(label).map(|n| n.to_string()) }
+        };
+        let number = |label: &str| label.parse::<usize>().ok();
+
+        match self {
+            LabelScheme::Numbers => Some(cell.to_string()),
+            LabelScheme::LetterColumns | LabelScheme::LetterRows => {
+                let (row, col) = cell.split_once(',').unwrap_or((cell, ""));
+                let (row, col) = match self {
+                    LabelScheme::LetterRows => (convert(row, parse_letters)?, convert(col, number)?),
+                    _ => (convert(row, number)?, convert(col, parse_letters)?),
+                };
+                Some(if col.is_empty() && !cell.contains(',') { row } else { format!("{row},{col}") })
+            },
+            LabelScheme::Chess => {
+                if cell.is_empty() {
+                    return Some(String::new());
+                }
+                let split = cell.find(|c: char| c.is_ascii_digit())?;
+                let col = parse_letters(&cell[..split])?;
+                let row = number(&cell[split..]).filter(|row| (1..=rows).contains(row))?;
+                Some(format!("{},{col}", rows + 1 - row))
+            },
+        }
+    }
+}
+
+// Letters labeling `n' (counted from 1): a to z, then aa, ab and so on.
+fn letters(mut n: usize) -> String {
+    let mut letters = Vec::new();
+    while n > 0 {
+        n -= 1;
+        letters.push((b'a' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+    letters.iter().rev().collect()
+}
+
+// Number labeled by `letters', in either case. Return None if there are other characters.
+fn parse_letters(letters: &str) -> Option<usize> {
+    if letters.is_empty() {
+        return None;
+    }
+    letters.chars().try_fold(0usize, |n, c| match c.to_ascii_lowercase() {
+        c @ 'a'..='z' => n.checked_mul(26)?.checked_add(c as usize - 'a' as usize + 1),
+        _ => None,
+    })
 }
 
 pub enum ExploreResult {
     Ok,
-    EmptyCache,
     Mined,
     BoardClear,
 }
 
+// Something found while exploring cells, besides their numbers.
+pub enum Finding {
+    Treasure,
+    PowerUp(PowerUp),
+    // A mine was hit, but one of the lives left was lost instead of the game. The number of
+    // lives left then is given.
+    Exploded(usize),
+}
+
+// Summary of a call to Board::explore(): what was found, in order, and how the game stands.
+pub struct Exploration {
+    pub findings: Vec<Finding>,
+    pub result: ExploreResult,
+}
+
 pub enum CacheResult {
     Ok,
     InvalidCoordinate,
     Clear,
+    AlreadyQueued,
+    Flagged,
+    // The cell is a mine that already went off.
+    Exploded,
+}
+
+pub enum ChordResult {
+    Ok,
+    InvalidCoordinate,
+    Unexplored,
+    Unsatisfied,
+    // Chording would tell whether the flags match the true number, which liar boards hide.
+    LiarBoard,
+}
+
+pub enum ExpandResult {
+    Ok,
+    // The board would have more than MAX_EXPANDED_AREA cells.
+    TooLarge,
+}
+
+pub enum DefuseResult {
+    Defused,
+    NotMined,
+    InvalidCoordinate,
+    Clear,
+    NotAdjacent,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FlagPolicy {
+    // Any number of cells may be flagged.
+    Unlimited,
+    // No more cells than there are mines may be flagged.
+    Strict,
 }
 
+pub enum FlagResult {
+    Flagged,
+    Unflagged,
+    Questioned,
+    AlreadyExplored,
+    OutOfBounds,
+    LimitReached,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum CellLabel {
     Clear,
-    Flag,
+    Treasure,
     MinedNeighbors(usize)
 }
 
+// What the player may see of a cell.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CellView {
+    Hidden,
+    Flagged,
+    Questioned,
+    // Explored, with the number shown (off by one on liar boards) or the treasure found there.
+    Revealed(CellLabel),
+    // A mine hit at the cost of a life.
+    Exploded,
+    // Once the game is lost: a mine that was not flagged, and a flag on a cell that is not mined.
+    Mine,
+    WrongFlag,
+}
+
+/* With the `serde' feature, boards can be serialized and deserialized with all their state,
+ * except for the moves that can be undone or redone, and the generator of chance: deserialized
+ * boards draw from a new one. Nothing about how the board is shown is stored, as it is rendered
+ * from the state of its cells. Deserialized boards can be checked with Board::validate().
+ */
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Board {
     // Dimensions of the board.
     rows: usize,
     cols: usize,
     area: usize,
 
-    // Number of cells marked as mined.
-    flagged: HashSet<Coord>,
+    // State of every cell, row by row.
+    cells: Vec<Vec<Cell>>,
 
-    // Cells to be explored in the next call to Board::explore().
+    // Number of cells explored (and clear).
+    explored: usize,
+
+    // Whether the first cell explored is kept free of mined neighbors, so that it opens up.
+    zero_opening: bool,
+
+    // Number of mines that can still be hit without losing the game.
+    lives: usize,
+
+    // Number of flags placed on cells that are not mined during the game.
+    wrong_flags: usize,
+
+    // Cells to be explored, with their neighborhoods, in the next call to Board::explore().
     cached: HashSet<Coord>,
 
-    // Cells already explored (and clear).
-    clear: HashSet<Coord>,
+    // Rule deciding which cells are neighbors.
+    adjacency: Adjacency,
+
+    // Whether the edges of the board wrap around, left to right and top to bottom.
+    torus: bool,
+
+    // How rows and columns are labeled.
+    label_scheme: LabelScheme,
+
+    // Whether the numbers are off by one (see Cell::lies_up).
+    liar: bool,
+
+    // Whether every mine is shown, once the game is lost.
+    mines_shown: bool,
 
-    // Location of each coordinate on the board.
-    mines_at: HashSet<Coord>,
+    // Seed the board was created from, and generator of everything left to chance on the board
+    // since then (e.g. where the first mine explored is moved).
+    seed: u64,
+    #[cfg_attr(feature = "serde", serde(skip, default = "StdRng::from_entropy"))]
+    rng: StdRng,
 
-    // The string representation of the board.
-    board_string: String,
+    // States of the board before each move that can be taken back, most recent last, and
+    // after each move taken back, for redoing it.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    undo_stack: Vec<BoardSnapshot>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    redo_stack: Vec<BoardSnapshot>,
 
-    // The label of each coordinate in the `board_string' array.
-    labels: HashMap<Coord, usize>,
+    // State of the board once the first cell was explored, which Board::reset() goes back to.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    start: Option<BoardSnapshot>,
+}
+
+// State of play of a board at some point, to go back to it later with Board::restore(), e.g. to
+// undo moves or to look ahead. The rules of the board (adjacency, labels, liar numbers...) are
+// not part of it.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoardSnapshot {
+    rows: usize,
+    cols: usize,
+    cells: Vec<Vec<Cell>>,
+    explored: usize,
+    lives: usize,
+    wrong_flags: usize,
+    cached: HashSet<Coord>,
+    mines_shown: bool,
+    #[cfg_attr(feature = "serde", serde(skip, default = "StdRng::from_entropy"))]
+    rng: StdRng,
 }
 
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.board_string)
+        write!(f, "{}", AsciiRenderer.render(self))
     }
 }
 
 impl Board {
 
     pub fn new(rows: usize, cols: usize, mine_count: usize) -> Result<Self, BoardError> {
+        Board::new_seeded(rows, cols, mine_count, rand::random())
+    }
+
+    // Create a board whose mines, and everything else left to chance, are drawn from `seed', so
+    // that the same seed always gives the same board.
+    pub fn new_seeded(rows: usize, cols: usize, mine_count: usize, seed: u64) -> Result<Self, BoardError> {
 
         let board_area = rows * cols;
 
@@ -75,62 +365,308 @@ impl Board {
             return Err(BoardError::TooManyMines);
         }
 
-        let row_label_width = rows.to_string().len() + 1;
-        let col_label_width = cols.to_string().len() + 2;
+        /* Mine the board by randomly placing exactly `mine_count'
+         * mines. Cell indices are sampled without replacement, so
+         * that no cell is drawn more than once.
+         */
 
-        let mut board_string: String =
-            String::with_capacity(((col_label_width + 2) * cols + (row_label_width + 2)) * rows);
+        let mut rng = StdRng::seed_from_u64(seed);
 
-        // Header including column labels and separators.
-        board_string.push_str(format!("{:width$}|", 1,
-                                      width = (row_label_width + 1) +
-                                      col_label_width).as_str());
+        let mines_at: HashSet<Coord> = index::sample(&mut rng, board_area, mine_count)
+            .into_iter()
+            .map(|index| (index/cols, index%cols))
+            .collect();
 
-        for col in 2..(cols + 1) {
-            board_string.push_str(format!("{:col_label_width$}|", col).as_str());
-        }
+        Ok(Board::with_mines(rows, cols, mines_at, seed))
+    }
 
-        board_string.push('\n');
+    // Create a board whose mines are laid out symmetrically. Mines are placed in orbits, i.e.
+    // pairs of cells mapped to each other by `symmetry', or single cells mapped to themselves
+    // (on the axis or at the center of boards with odd dimensions). The board has exactly
+    // `mine_count' mines, unless the count is odd and there are no single-cell orbits, in which
+    // case it has one mine less.
+    pub fn new_symmetric(rows: usize, cols: usize, mine_count: usize, symmetry: Symmetry, seed: u64)
+                         -> Result<Self, BoardError> {
 
-        // Row and cell labels.
-        for row in 0..rows {
-            board_string.push_str(format!("{:row_label_width$}|", row + 1).as_str());
-            board_string.push_str(format!("{:>width$}", '.', width = col_label_width + 1)
-                                  .repeat(cols).as_str());
-            board_string.push('\n');
+        if rows * cols == 0 {
+            return Err(BoardError::NullArea);
         }
 
-        /* Mine the board by randomly placing (approximately)
-         * `mine_count' mines. Note that it is possible that the
-         * actual number of mines is less than `mine_count' as the
-         * same point may be drawn from the distribution more than
-         * once. TODO: handle this case.
-         */
+        if rows * cols <= mine_count {
+            return Err(BoardError::TooManyMines);
+        }
 
-        let mut rng = rand::thread_rng();
-        let uniform: Uniform<usize> = Uniform::new(0, board_area);
+        let image = |(row, col): Coord| match symmetry {
+            Symmetry::Horizontal => (row, cols - 1 - col),
+            Symmetry::Vertical => (rows - 1 - row, col),
+            Symmetry::Rot180 => (rows - 1 - row, cols - 1 - col),
+        };
 
-        let mines_at: HashSet<Coord> = uniform
-            .sample_iter(&mut rng)
-            .take(mine_count)
-            .map(|index| (index/cols, index%cols))
+        // Each orbit is listed once, from the cell that comes first.
+        let mut orbits: Vec<Vec<Coord>> = (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| (row, col)))
+            .filter(|&coord| coord <= image(coord))
+            .map(|coord| if coord == image(coord) { vec![coord] } else { vec![coord, image(coord)] })
             .collect();
 
-        Ok(Board {
+        orbits.shuffle(&mut StdRng::seed_from_u64(seed));
+
+        let mut mines_at: HashSet<Coord> = HashSet::with_capacity(mine_count);
+
+        for orbit in orbits {
+            if mines_at.len() + orbit.len() <= mine_count {
+                mines_at.extend(orbit);
+            }
+        }
+
+        Ok(Board::with_mines(rows, cols, mines_at, seed))
+    }
+
+    /* Create a board that can be solved by deduction alone, without guessing, from an opening
+     * cell without mined neighbors. Boards are generated at random until one of them can be
+     * solved by the same deductions as hints, at most NO_GUESS_ATTEMPTS times. The opening is
+     * queued for exploration in the returned board.
+     */
+    pub fn new_no_guess(rows: usize, cols: usize, mine_count: usize, adjacency: Adjacency, torus: bool,
+                        seed: u64) -> Result<Self, BoardError> {
+
+        let board_area = rows * cols;
+
+        if board_area == 0 {
+            return Err(BoardError::NullArea);
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        for _ in 0..NO_GUESS_ATTEMPTS {
+
+            let mut board = Board::with_mines(rows, cols, HashSet::new(), seed);
+            board.adjacency = adjacency;
+            board.torus = torus;
+
+            // The opening and its neighbors are kept free of mines.
+            let opening = (rng.gen_range(0..rows), rng.gen_range(0..cols));
+            let mut candidates: Vec<Coord> = (0..rows)
+                .flat_map(|row| (0..cols).map(move |col| (row, col)))
+                .collect();
+            let zone = board.neighbors(opening);
+            candidates.retain(|coord| *coord != opening && !zone.contains(coord));
+
+            if candidates.len() < mine_count {
+                return Err(BoardError::TooManyMines);
+            }
+
+            for &coord in candidates.choose_multiple(&mut rng, mine_count) {
+                board.place_mine(coord);
+            }
+
+            if board.is_solvable_from(opening) {
+                debug!("found a no-guess board with its opening at ({}, {})", opening.0 + 1, opening.1 + 1);
+                board.cached.insert(opening);
+                return Ok(board);
+            }
+        }
+
+        Err(BoardError::NoGuessNotFound)
+    }
+
+    // Tell whether every safe cell can be explored from `opening' by deduction alone.
+    fn is_solvable_from(&self, opening: Coord) -> bool {
+
+        let mut explored: HashSet<Coord> = HashSet::new();
+        let mut mined: HashSet<Coord> = HashSet::new();
+        let mut queued: Vec<Coord> = vec![opening];
+
+        loop {
+            // Explore the queued cells, and the neighbors of those without mined neighbors.
+            while let Some(coord) = queued.pop() {
+                if explored.insert(coord) && self.mined_neighbors(coord) == 0 {
+                    queued.extend(self.neighbors(coord).into_iter().filter(|ng| !explored.contains(ng)));
+                }
+            }
+
+            queued.extend(self.deduce(&explored, &mut mined));
+
+            if queued.is_empty() {
+                return explored.len() + self.get_mine_count() == self.area;
+            }
+        }
+    }
+
+    fn with_mines(rows: usize, cols: usize, mines_at: HashSet<Coord>, seed: u64) -> Self {
+
+        let board_area = rows * cols;
+        let mut cells = vec![vec![Cell::default(); cols]; rows];
+        for &(row, col) in &mines_at {
+            cells[row][col].mined = true;
+        }
+
+        let mut board = Board {
             rows,
             cols,
             area: board_area,
-            flagged: HashSet::with_capacity(mines_at.len()),
+            cells,
+            explored: 0,
+            zero_opening: false,
+            lives: 0,
+            wrong_flags: 0,
             cached: HashSet::with_capacity(board_area - mines_at.len()),
-            clear: HashSet::with_capacity(board_area - mines_at.len()),
-            mines_at,
-            labels: board_string
-                .match_indices('.')
-                .enumerate()
-                .map(|(n, (index, _))| ((n/cols, n % cols), index))
-                .collect::<HashMap<Coord, usize>>(),
-            board_string,
-        })
+            adjacency: Adjacency::Radius(1),
+            torus: false,
+            label_scheme: LabelScheme::Numbers,
+            liar: false,
+            mines_shown: false,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            start: None,
+        };
+
+        board.count_mined_neighbors();
+        board
+    }
+
+    // Append `rows' rows and `cols' columns to the board, mined at the current density of
+    // mines. The progress made so far is kept, and explored cells without mined neighbors
+    // cache their new neighbors for exploration. The moves made so far become final.
+    pub fn expand(&mut self, rows: usize, cols: usize) -> ExpandResult {
+
+        let area = self.rows.checked_add(rows)
+            .zip(self.cols.checked_add(cols))
+            .and_then(|(rows, cols)| rows.checked_mul(cols));
+        if area.is_none_or(|area| area > MAX_EXPANDED_AREA) {
+            return ExpandResult::TooLarge;
+        }
+
+        self.forget_moves();
+
+        let (old_rows, old_cols) = (self.rows, self.cols);
+        let density = self.get_mine_count() as f64 / self.area as f64;
+
+        self.rows += rows;
+        self.cols += cols;
+        self.area = self.rows * self.cols;
+
+        for cells in &mut self.cells {
+            cells.resize(self.cols, Cell::default());
+        }
+        self.cells.resize(self.rows, vec![Cell::default(); self.cols]);
+
+        let new_cells: Vec<Coord> = self.coords()
+            .filter(|&(row, col)| row >= old_rows || col >= old_cols)
+            .collect();
+
+        let mine_count = (density * new_cells.len() as f64).round() as usize;
+        for (row, col) in new_cells.iter().copied().choose_multiple(&mut self.rng, mine_count) {
+            self.cells[row][col].mined = true;
+        }
+
+        // On liar boards, the numbers of the new cells are off by one as well.
+        if self.liar {
+            for (row, col) in new_cells {
+                self.cells[row][col].lies_up = self.rng.gen_bool(0.5);
+            }
+        }
+
+        self.count_mined_neighbors();
+
+        // Explored cells whose neighborhood reaches into the new cells.
+        let frontier: Vec<Coord> = self.coords()
+            .filter(|&coord| self.cell(coord).is_explored())
+            .filter(|&coord| self.neighbors(coord).into_iter().any(|(row, col)| row >= old_rows || col >= old_cols))
+            .collect();
+
+        for coord in frontier {
+            self.cache_neighbors_if_clear(coord);
+        }
+
+        ExpandResult::Ok
+    }
+
+    // Change the adjacency rule of the board, updating the numbers of the explored cells.
+    pub fn set_adjacency(&mut self, adjacency: Adjacency) {
+        self.adjacency = adjacency;
+        self.count_mined_neighbors();
+    }
+
+    // Keep the first cell explored free of mined neighbors, or not.
+    pub fn set_zero_opening(&mut self, zero_opening: bool) {
+        self.zero_opening = zero_opening;
+    }
+
+    // Let `lives' mines be hit before the game is lost.
+    pub fn set_lives(&mut self, lives: usize) {
+        self.lives = lives;
+    }
+
+    // Make the edges of the board wrap around, or not, updating the numbers of the explored cells.
+    pub fn set_torus(&mut self, torus: bool) {
+        self.torus = torus;
+        self.count_mined_neighbors();
+    }
+
+    pub fn is_torus(&self) -> bool {
+        self.torus
+    }
+
+    // Change how rows and columns are labeled.
+    pub fn set_label_scheme(&mut self, scheme: LabelScheme) {
+        self.label_scheme = scheme;
+    }
+
+    // Label of the cell at `coord' (counted from 1), as typed in commands.
+    pub fn cell_label(&self, coord: Coord) -> String {
+        self.label_scheme.cell_label(coord, self.rows)
+    }
+
+    // Turn the board into a liar board, where every number is randomly off by one, up or down.
+    pub fn make_liar(&mut self) {
+
+        self.liar = true;
+        for cell in self.cells.iter_mut().flatten() {
+            cell.lies_up = self.rng.gen_bool(0.5);
+        }
+    }
+
+    pub fn is_liar(&self) -> bool {
+        self.liar
+    }
+
+    // Rotate the board 90 degrees clockwise.
+    pub fn rotate90(&mut self) {
+        let rows = self.rows;
+        self.transform(self.cols, self.rows, |(row, col)| (col, rows - 1 - row));
+    }
+
+    // Mirror the board horizontally, swapping its left and right sides.
+    pub fn mirror_h(&mut self) {
+        let cols = self.cols;
+        self.transform(self.rows, self.cols, |(row, col)| (row, cols - 1 - col));
+    }
+
+    // Mirror the board vertically, swapping its top and bottom sides.
+    pub fn mirror_v(&mut self) {
+        let rows = self.rows;
+        self.transform(self.rows, self.cols, |(row, col)| (rows - 1 - row, col));
+    }
+
+    // Move every cell (and its state) to the coordinate given by `map' on a board of
+    // `rows' x `cols' cells. The moves made so far become final.
+    fn transform(&mut self, rows: usize, cols: usize, map: impl Fn(Coord) -> Coord) {
+
+        self.forget_moves();
+
+        let mut cells = vec![vec![Cell::default(); cols]; rows];
+        for coord in self.coords() {
+            let (row, col) = map(coord);
+            cells[row][col] = *self.cell(coord);
+        }
+
+        self.cells = cells;
+        self.cached = self.cached.iter().map(|&coord| map(coord)).collect();
+        self.rows = rows;
+        self.cols = cols;
     }
 
     pub fn cache(&mut self, mut coord: Coord) -> CacheResult {
@@ -143,155 +679,1515 @@ impl Board {
             return CacheResult::InvalidCoordinate;
         }
 
-        if self.clear.contains(&(coord.0, coord.1)) {
-            return CacheResult::Clear;
+        match self.cell(coord).state {
+            CellState::Revealed => return CacheResult::Clear,
+            CellState::Flagged => return CacheResult::Flagged,
+            CellState::Exploded => return CacheResult::Exploded,
+            CellState::Hidden | CellState::Questioned => (),
+        }
+
+        if !self.cached.insert((coord.0, coord.1)) {
+            return CacheResult::AlreadyQueued;
         }
 
-        self.cached.insert((coord.0, coord.1));
+        debug!("cached ({}, {})", coord.0 + 1, coord.1 + 1);
         CacheResult::Ok
     }
 
-    fn reveal_mines(&mut self) {
-        for coord in &self.mines_at {
-            let &index = self.labels.get(coord).unwrap();
-            self.board_string.replace_range(index..(index + 1), "*");
-        }
-    }
-
-    pub fn update_label(&mut self, mut at: Coord, label: CellLabel, from_ui: bool) -> bool {
-
-        // Coordinates passed in from UI calls are offset by (1, 1).
-        if from_ui {
-            at.0 -= 1;
-            at.1 -= 1;
-        }
-
-        // Buffer to replace a single char in `board_string'.
-        if let Some(&index) = self.labels.get(&at) {
-   
-            let mut buffer: [u8; 2] = [0; 2];
-   
-            match label {
-                CellLabel::Clear => {
-                    self.flagged.remove(&at);
-                    self.board_string.replace_range(index..(index + 1),
-                                                    ' '.encode_utf8(&mut buffer));
-                },
-                CellLabel::MinedNeighbors(mine_count) => {
-                    self.flagged.remove(&at);
-                    self.board_string.replace_range(index..(index + 1),
-                                                    mine_count.to_string().chars()
-                                                    .next().unwrap().encode_utf8(&mut buffer));
-                },
-                CellLabel::Flag => {
-                    // Do nothing if the parcel has already been explored.
-                    // Otherwise, exchange '>' for '.' and vice-versa.
-
-                    if self.flagged.remove(&at) {
-                        self.board_string.replace_range(index..(index + 1),
-                                                        '.'.encode_utf8(&mut buffer));
-                    }
-                    else if self.flagged.len() < self.mines_at.len() {
-                        self.flagged.insert(at);
-                        self.board_string.replace_range(index..(index + 1),
-                                                        '>'.encode_utf8(&mut buffer));
-                    }
-                }
-            }
+    // Cache every coordinate in `coords', reporting the result for each of them.
+    pub fn cache_many(&mut self, coords: impl IntoIterator<Item = Coord>) -> Vec<(Coord, CacheResult)> {
+        coords
+            .into_iter()
+            .map(|coord| (coord, self.cache(coord)))
+            .collect()
+    }
 
-            return true;
+    // Hide (at most) `count' treasures in randomly chosen cells that are not mined.
+    pub fn hide_treasures(&mut self, count: usize) {
+
+        let cells = self.coords()
+            .filter(|&(row, col)| !self.cells[row][col].mined)
+            .choose_multiple(&mut self.rng, count);
+
+        for cell in self.cells.iter_mut().flatten() {
+            cell.treasure = false;
         }
+        for coord in cells {
+            self.cell_mut(coord).treasure = true;
+        }
+    }
 
-        false
+    // Hide (at most) `count' power-ups of random kinds in cells with neither mines nor treasures.
+    pub fn hide_power_ups(&mut self, count: usize) {
 
+        let cells = self.coords()
+            .filter(|&(row, col)| !self.cells[row][col].mined && !self.cells[row][col].treasure)
+            .choose_multiple(&mut self.rng, count);
+
+        for cell in self.cells.iter_mut().flatten() {
+            cell.power_up = None;
+        }
+        for coord in cells {
+            let power_up = if self.rng.gen_bool(0.5) { PowerUp::Radar } else { PowerUp::Defuse };
+            self.cell_mut(coord).power_up = Some(power_up);
+        }
     }
 
-    pub fn get_rows(&self) -> usize {
-        self.rows
+    // Place a new mine in a random unexplored cell, as long as another unexplored cell is left
+    // safe, and update the numbers of its explored neighbors. Return where the mine was placed.
+    pub fn add_mine(&mut self) -> Option<Coord> {
+
+        let candidates: Vec<Coord> = self.coords()
+            .filter(|&coord| !self.cell(coord).is_explored() && !self.cell(coord).mined)
+            .collect();
+
+        if candidates.len() < 2 {
+            return None;
+        }
+
+        let &coord = candidates.choose(&mut self.rng)?;
+        self.forget_moves();
+        debug!("added a mine at ({}, {})", coord.0 + 1, coord.1 + 1);
+        self.place_mine(coord);
+
+        let explored: Vec<Coord> = self.neighbors(coord)
+            .into_iter()
+            .filter(|&ng| self.cell(ng).is_explored())
+            .collect();
+
+        for neighbor in explored {
+            self.cache_neighbors_if_clear(neighbor);
+        }
+
+        Some(coord)
     }
 
-    pub fn get_cols(&self) -> usize {
-        self.cols
+    // Move a random mine to a random unexplored cell that is not mined, and update the numbers
+    // of the explored cells around both. Return whether a mine was moved.
+    pub fn relocate_mine(&mut self) -> bool {
+
+        let Some(from) = self.coords()
+            .filter(|&(row, col)| self.cells[row][col].mined && !self.cells[row][col].is_exploded())
+            .choose(&mut self.rng) else {
+            return false };
+
+        self.forget_moves();
+        self.move_mine(from)
     }
 
-    pub fn get_mine_count(&self) -> usize {
-        self.mines_at.len()
+    // Save the state of play of the board.
+    pub fn snapshot(&self) -> BoardSnapshot {
+        BoardSnapshot {
+            rows: self.rows,
+            cols: self.cols,
+            cells: self.cells.clone(),
+            explored: self.explored,
+            lives: self.lives,
+            wrong_flags: self.wrong_flags,
+            cached: self.cached.clone(),
+            mines_shown: self.mines_shown,
+            rng: self.rng.clone(),
+        }
     }
 
-    pub fn get_flagged_count(&self) -> usize {
-        self.flagged.len()
+    // Put the board back in the state of play saved in `snapshot'. Moves that can be undone or
+    // redone are kept.
+    pub fn restore(&mut self, snapshot: &BoardSnapshot) {
+        self.rows = snapshot.rows;
+        self.cols = snapshot.cols;
+        self.area = snapshot.rows * snapshot.cols;
+        self.cells.clone_from(&snapshot.cells);
+        self.explored = snapshot.explored;
+        self.lives = snapshot.lives;
+        self.wrong_flags = snapshot.wrong_flags;
+        self.cached.clone_from(&snapshot.cached);
+        self.mines_shown = snapshot.mines_shown;
+        self.rng = snapshot.rng.clone();
     }
 
-    pub fn explore(&mut self) -> ExploreResult {
+    // Record `before', the state of the board before the move just made, so that the move can
+    // be taken back. Moves taken back before can no longer be redone.
+    pub fn record_move(&mut self, before: BoardSnapshot) {
+        self.undo_stack.push(before);
+        self.redo_stack.clear();
+    }
 
-        // Get the next cell to explore.
-        // In passing, check if all mines have been found.
+    // Put the board back as it was before the first move: nothing explored, flagged or queued
+    // for exploration, with the mines, treasures and power-ups it had once the first cell was
+    // explored, even if some were added, moved, defused or found since.
+    pub fn reset(&mut self) {
+        if let Some(start) = self.start.clone() {
+            self.restore(&start);
+        }
+        for cell in self.cells.iter_mut().flatten() {
+            cell.reset();
+        }
+        self.explored = 0;
+        self.wrong_flags = 0;
+        self.mines_shown = false;
+        self.cached.clear();
+        self.forget_moves();
+    }
 
-        if self.cached.is_empty() {
+    // Make the moves made so far final, e.g. once something was found that cannot be put back.
+    pub fn forget_moves(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
 
-            if self.area - self.clear.len() == self.mines_at.len() {
-                return ExploreResult::BoardClear;
-            }
-            else {
-                return ExploreResult::EmptyCache;
-            }
-        }
+    // Take back the last move. Return whether there was one.
+    pub fn undo(&mut self) -> bool {
+        let Some(before) = self.undo_stack.pop() else {
+            return false };
+
+        let after = self.switch_to(&before);
+        self.redo_stack.push(after);
+        true
+    }
+
+    // Make again the last move taken back. Return whether there was one.
+    pub fn redo(&mut self) -> bool {
+        let Some(after) = self.redo_stack.pop() else {
+            return false };
+
+        let before = self.switch_to(&after);
+        self.undo_stack.push(before);
+        true
+    }
+
+    // Restore the state of play `state' and return the one before, for undoing and redoing
+    // moves. Wrong flags are still counted, so that taking them back does not spare their penalty.
+    fn switch_to(&mut self, state: &BoardSnapshot) -> BoardSnapshot {
+        let current = self.snapshot();
+        self.restore(state);
+        self.wrong_flags = current.wrong_flags;
+        current
+    }
+
+    // Move the mine at `from' to a random cell that is neither explored, mined nor queued for
+    // exploration, and update the numbers of the explored cells around both. Return whether
+    // there was such a cell.
+    fn move_mine(&mut self, from: Coord) -> bool {
+
+        let Some(to) = self.coords()
+            .filter(|&(row, col)| !self.cells[row][col].is_explored() && !self.cells[row][col].mined)
+            .filter(|coord| !self.cached.contains(coord))
+            .choose(&mut self.rng) else {
+            return false };
+
+        debug!("moved the mine at ({}, {}) to ({}, {})", from.0 + 1, from.1 + 1, to.0 + 1, to.1 + 1);
+        self.remove_mine(from);
+        self.place_mine(to);
 
-        let &(row, col) = self.cached.iter().next().unwrap();
-        self.cached.remove(&(row, col));
+        let explored: Vec<Coord> = self.neighbors(from)
+            .into_iter()
+            .chain(self.neighbors(to))
+            .filter(|&ng| self.cell(ng).is_explored())
+            .collect();
 
-        // If the cell is mined, return.
-        if self.mines_at.contains(&(row, col)) {
-            self.reveal_mines();
-            return ExploreResult::Mined;
+        for neighbor in explored {
+            self.cache_neighbors_if_clear(neighbor);
         }
 
-        self.clear.insert((row, col));
+        true
+    }
 
-        // Possible coordinates of each neighbor.
-        let above = row.checked_sub(1);
-        let below = if row + 1 < self.rows { Some(row + 1) } else { None };
-        let left = col.checked_sub(1);
-        let right = if col + 1 < self.cols { Some(col + 1) } else { None };
+    // Move the mines at `coord' and around it to random cells further away, that are neither
+    // mined nor queued for exploration, starting with the one at `coord'. On boards too small
+    // for that, the mines that do not fit elsewhere stay where they are.
+    fn clear_opening(&mut self, coord: Coord) {
 
-        // Neighbors not yet explored and candidate for exploration.
-        let mut unexplored: Vec<Coord> = Vec::with_capacity(8);
+        let mut zone = self.neighbors(coord);
+        zone.insert(0, coord);
 
-        // Number of mines found in the neighborhood.
-        let mut mined: usize = 0;
+        let mined: Vec<Coord> = zone.iter().copied().filter(|&cell| self.cell(cell).mined).collect();
 
-        // Immutable variable defined to improve readability.
-        let neighborhood =
-            [ (above,      left), (above, Some(col)), (above,     right),
-               (Some(row), left),                     (Some(row), right),
-               (below,     left), (below, Some(col)), (below,     right) ];
+        let targets = self.coords()
+            .filter(|&(row, col)| !self.cells[row][col].mined)
+            .filter(|cell| !zone.contains(cell) && !self.cached.contains(cell))
+            .choose_multiple(&mut self.rng, mined.len());
 
-        for neighbor in neighborhood {
+        for (from, to) in mined.into_iter().zip(targets) {
+            debug!("moved the mine at ({}, {}) to ({}, {}) for the opening", from.0 + 1, from.1 + 1, to.0 + 1, to.1 + 1);
+            self.remove_mine(from);
+            self.place_mine(to);
+        }
+    }
 
-            // Filter neighbors with valid coordinates.
-            if let (Some(ng_row), Some(ng_col)) = neighbor {
+    // Put a mine at `coord', which can then hide neither a treasure nor a power-up.
+    fn place_mine(&mut self, coord: Coord) {
+        let cell = self.cell_mut(coord);
+        cell.mined = true;
+        cell.treasure = false;
+        cell.power_up = None;
 
-                if self.mines_at.contains(&(ng_row, ng_col)) {
-                    mined += 1; // Mined neighbor.
-                }
-                else if mined == 0 && !self.clear.contains(&(ng_row, ng_col)) {
-                    // If no mines have been found in the neighborhood yet, and the current cell has
-                    // not been explored, then make it a candidate for exploration in a subsequent
-                    // call to this function.
-                    unexplored.push((ng_row, ng_col));
-                }
-            }
+        for neighbor in self.neighbors(coord) {
+            self.cell_mut(neighbor).adjacent += 1;
         }
+    }
 
-        if mined > 0 {
-            self.update_label((row, col), CellLabel::MinedNeighbors(mined), false);
+    fn remove_mine(&mut self, coord: Coord) {
+        self.cell_mut(coord).mined = false;
+
+        for neighbor in self.neighbors(coord) {
+            self.cell_mut(neighbor).adjacent -= 1;
         }
-        else {
-            // Cache unexplored neighbors.
-            self.update_label((row, col), CellLabel::Clear, false);
-            self.cached.extend(unexplored);
+    }
+
+    // Tell whether there are mines in the 3x3 area centered at `coord', whatever the adjacency
+    // rule of the board. The area wraps around the edges of a torus.
+    pub fn radar(&self, mut coord: Coord) -> Option<bool> {
+
+        // Coordinates as specified by the user are offset by 1.
+        coord.0 -= 1;
+        coord.1 -= 1;
+
+        if !(coord.0 < self.rows && coord.1 < self.cols) {
+            return None;
         }
 
-        ExploreResult::Ok
+        let span = |value: usize, size: usize| -> Vec<usize> {
+            match self.torus {
+                true => vec![(value + size - 1) % size, value, (value + 1) % size],
+                false => (value.saturating_sub(1)..=(value + 1).min(size - 1)).collect(),
+            }
+        };
+
+        let cols = span(coord.1, self.cols);
+        Some(span(coord.0, self.rows)
+             .into_iter()
+             .any(|row| cols.iter().any(|&col| self.cells[row][col].mined)))
+    }
+
+    // Remove the mine (if any) at `coord', which must be next to an explored cell, and cache the
+    // cell for exploration. The labels of the explored neighbors are updated accordingly. Since
+    // the power-up used cannot be given back, the moves made so far become final.
+    pub fn defuse(&mut self, mut coord: Coord) -> DefuseResult {
+
+        // Coordinates as specified by the user are offset by 1.
+        coord.0 -= 1;
+        coord.1 -= 1;
+
+        if !(coord.0 < self.rows && coord.1 < self.cols) {
+            return DefuseResult::InvalidCoordinate;
+        }
+
+        if self.cell(coord).is_explored() {
+            return DefuseResult::Clear;
+        }
+
+        let explored: Vec<Coord> = self.neighbors(coord)
+            .into_iter()
+            .filter(|&ng| self.cell(ng).is_explored())
+            .collect();
+
+        if explored.is_empty() {
+            return DefuseResult::NotAdjacent;
+        }
+
+        self.forget_moves();
+        self.cached.insert(coord);
+
+        if !self.cell(coord).mined {
+            return DefuseResult::NotMined;
+        }
+        self.remove_mine(coord);
+        if self.cell(coord).is_exploded() {
+            self.cell_mut(coord).reset();
+        }
+
+        for neighbor in explored {
+            self.cache_neighbors_if_clear(neighbor);
+        }
+
+        DefuseResult::Defused
+    }
+
+    pub fn chord(&mut self, mut coord: Coord) -> ChordResult {
+
+        if self.liar {
+            return ChordResult::LiarBoard;
+        }
+
+        // Coordinates as specified by the user are offset by 1.
+        coord.0 -= 1;
+        coord.1 -= 1;
+
+        if !(coord.0 < self.rows && coord.1 < self.cols) {
+            return ChordResult::InvalidCoordinate;
+        }
+
+        if !self.cell(coord).is_explored() {
+            return ChordResult::Unexplored;
+        }
+
+        let neighbors = self.neighbors(coord);
+        let mined = self.mined_neighbors(coord);
+        let flagged = neighbors.iter().filter(|&&ng| self.cell(ng).is_flagged() || self.cell(ng).is_exploded()).count();
+
+        if mined != flagged {
+            return ChordResult::Unsatisfied;
+        }
+
+        // Cache every neighbor neither flagged, explored yet, nor exploded.
+        self.cached.extend(neighbors.into_iter()
+                           .filter(|&(row, col)| matches!(self.cells[row][col].state,
+                                                          CellState::Hidden | CellState::Questioned)));
+        ChordResult::Ok
+    }
+
+    // Find an unexplored cell, not flagged, that is provably safe given the numbers shown, and
+    // return its coordinate as typed by the user. Numbers hidden by treasures are not used.
+    pub fn safe_cell(&self) -> Option<Coord> {
+
+        let explored: HashSet<Coord> = self.coords().filter(|&coord| self.cell(coord).is_explored()).collect();
+        let mut exploded: HashSet<Coord> = self.coords().filter(|&coord| self.cell(coord).is_exploded()).collect();
+
+        self.deduce(&explored, &mut exploded)
+            .into_iter()
+            .filter(|&coord| !self.cell(coord).is_flagged())
+            .min()
+            .map(|(row, col)| (row + 1, col + 1))
+    }
+
+    /* Find unexplored cells that are provably safe when the cells in `explored' show their
+     * numbers, adding the cells found to be mined along the way to `mined'. Each number gives
+     * a constraint: so many mines among its unexplored neighbors not known to be mined. A
+     * constraint asking for no mines makes its cells safe, and one asking for as many mines as
+     * it has cells makes them all mined. When the cells of a constraint are all part of those
+     * of another one, the difference of their counts applies in the same way to the cells
+     * left over. Deductions go on until safe cells are found or nothing new is learned.
+     */
+    fn deduce(&self, explored: &HashSet<Coord>, mined: &mut HashSet<Coord>) -> HashSet<Coord> {
+
+        let mut safe: HashSet<Coord> = HashSet::new();
+
+        loop {
+            let constraints: Vec<(Vec<Coord>, usize)> = explored
+                .iter()
+                .filter(|&&coord| !self.cell(coord).treasure)
+                .filter_map(|&coord| {
+                    let (known, unknown): (Vec<Coord>, Vec<Coord>) = self.neighbors(coord)
+                        .into_iter()
+                        .filter(|ng| !explored.contains(ng))
+                        .partition(|ng| mined.contains(ng));
+                    let count = self.mined_neighbors(coord) - known.len();
+                    (!unknown.is_empty()).then_some((unknown, count))
+                })
+                .collect();
+
+            let mut found_mines = false;
+
+            for (cells, count) in &constraints {
+                if *count == 0 {
+                    safe.extend(cells);
+                }
+                else if *count == cells.len() {
+                    for &cell in cells {
+                        found_mines |= mined.insert(cell);
+                    }
+                }
+            }
+
+            if safe.is_empty() && !found_mines {
+                for (small, small_count) in &constraints {
+                    for (large, large_count) in &constraints {
+
+                        if small.len() >= large.len() || !small.iter().all(|cell| large.contains(cell)) {
+                            continue;
+                        }
+
+                        let rest: Vec<Coord> = large.iter().filter(|cell| !small.contains(cell)).copied().collect();
+                        let count = large_count - small_count;
+
+                        if count == 0 {
+                            safe.extend(rest);
+                        }
+                        else if count == rest.len() {
+                            for cell in rest {
+                                found_mines |= mined.insert(cell);
+                            }
+                        }
+                    }
+                }
+            }
+
+            if !safe.is_empty() || !found_mines {
+                return safe;
+            }
+        }
+    }
+
+    // Coordinates of every cell, row by row.
+    fn coords(&self) -> impl Iterator<Item = Coord> {
+        let (rows, cols) = (self.rows, self.cols);
+        (0..rows).flat_map(move |row| (0..cols).map(move |col| (row, col)))
+    }
+
+    fn cell(&self, (row, col): Coord) -> &Cell {
+        &self.cells[row][col]
+    }
+
+    fn cell_mut(&mut self, (row, col): Coord) -> &mut Cell {
+        &mut self.cells[row][col]
+    }
+
+    fn mined_neighbors(&self, coord: Coord) -> usize {
+        self.cell(coord).adjacent
+    }
+
+    // Count the mined neighbors of every cell again, e.g. after a change in the adjacency rule.
+    fn count_mined_neighbors(&mut self) {
+        for coord in self.coords() {
+            self.cell_mut(coord).adjacent = self.neighbors(coord)
+                .into_iter()
+                .filter(|&ng| self.cell(ng).mined)
+                .count();
+        }
+    }
+
+    // Cache the unexplored neighbors of the explored cell at `coord' for exploration if a change
+    // in its neighborhood left it without mined neighbors, as explore() would have. Its number
+    // itself is kept up to date as mines are placed and removed.
+    fn cache_neighbors_if_clear(&mut self, coord: Coord) {
+
+        if self.mined_neighbors(coord) == 0 {
+            let unexplored: Vec<Coord> = self.neighbors(coord)
+                .into_iter()
+                .filter(|&ng| !self.cell(ng).is_explored() && !self.cell(ng).is_exploded())
+                .collect();
+            self.cached.extend(unexplored);
+        }
+    }
+
+    // Coordinates of the cells surrounding `(row, col)', as given by the adjacency rule of the
+    // board: up to eight of them with the standard radius of 1, up to 24 with a radius of 2,
+    // up to four when only orthogonal neighbors count...
+    fn neighbors(&self, (row, col): Coord) -> Vec<Coord> {
+
+        if self.torus {
+            return self.wrapped_neighbors((row, col));
+        }
+
+        match self.adjacency {
+            Adjacency::Radius(radius) => {
+
+                // Rows and columns of the square of side 2 * `radius' + 1 centered at the cell,
+                // clipped to the board.
+                let rows = row.saturating_sub(radius)..=row.saturating_add(radius).min(self.rows - 1);
+                let cols = col.saturating_sub(radius)..=col.saturating_add(radius).min(self.cols - 1);
+
+                rows
+                    .flat_map(|ng_row| cols.clone().map(move |ng_col| (ng_row, ng_col)))
+                    .filter(|&neighbor| neighbor != (row, col))
+                    .collect()
+            },
+            Adjacency::Orthogonal => {
+
+                let mut neighbors = Vec::with_capacity(4);
+                if row > 0 { neighbors.push((row - 1, col)); }
+                if row + 1 < self.rows { neighbors.push((row + 1, col)); }
+                if col > 0 { neighbors.push((row, col - 1)); }
+                if col + 1 < self.cols { neighbors.push((row, col + 1)); }
+                neighbors
+            },
+        }
+    }
+
+    // Neighbors of `(row, col)' on a torus, where cells on an edge are next to those on the
+    // opposite edge. On small boards, the same cell may be reached from both sides, and is
+    // only listed once.
+    fn wrapped_neighbors(&self, (row, col): Coord) -> Vec<Coord> {
+
+        let offsets: Vec<(isize, isize)> = match self.adjacency {
+            Adjacency::Radius(radius) => {
+                // Offsets beyond the size of the board only reach the same cells again.
+                let row_radius = radius.min(self.rows) as isize;
+                let col_radius = radius.min(self.cols) as isize;
+                (-row_radius..=row_radius)
+                    .flat_map(|d_row| (-col_radius..=col_radius).map(move |d_col| (d_row, d_col)))
+                    .collect()
+            },
+            Adjacency::Orthogonal => vec![(-1, 0), (1, 0), (0, -1), (0, 1)],
+        };
+
+        let wrap = |value: usize, offset: isize, size: usize| (value as isize + offset).rem_euclid(size as isize) as usize;
+
+        let mut neighbors: Vec<Coord> = offsets
+            .into_iter()
+            .map(|(d_row, d_col)| (wrap(row, d_row, self.rows), wrap(col, d_col, self.cols)))
+            .filter(|&neighbor| neighbor != (row, col))
+            .collect();
+
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        neighbors
+    }
+
+    // Flag every mine not flagged yet, e.g. once the board is cleared. Mines that went off keep
+    // their '*'.
+    fn flag_mines(&mut self) {
+
+        for cell in self.cells.iter_mut().flatten().filter(|cell| cell.mined) {
+            cell.flag();
+        }
+    }
+
+    // Show every mine on the board: flagged mines keep their flag, and the rest are shown as '*'.
+    // Flags on cells that are not mined are shown as 'X'.
+    pub fn reveal_mines(&mut self) {
+        self.mines_shown = true;
+    }
+
+    // Label of the cell at `coord' with `mined' mined neighbors. On liar boards the number is
+    // off by one, and it is shown even when it is 0 so that it is not mistaken for a cell whose
+    // neighbors were explored automatically.
+    fn number_label(&self, coord: Coord, mined: usize) -> CellLabel {
+
+        if !self.liar {
+            return if mined > 0 { CellLabel::MinedNeighbors(mined) } else { CellLabel::Clear };
+        }
+
+        if mined == 0 || self.cell(coord).lies_up {
+            CellLabel::MinedNeighbors(mined + 1)
+        }
+        else {
+            CellLabel::MinedNeighbors(mined - 1)
+        }
+    }
+
+    // Every cell of the board, row by row, with what the player may see of it. Coordinates are
+    // offset by 1, as typed by the user.
+    pub fn cells(&self) -> impl Iterator<Item = (Coord, CellView)> + '_ {
+        self.coords().map(|(row, col)| ((row + 1, col + 1), self.view((row, col))))
+    }
+
+    // What the player may see of the cell at `coord'.
+    fn view(&self, coord: Coord) -> CellView {
+
+        let cell = self.cell(coord);
+        match cell.state {
+            CellState::Hidden | CellState::Questioned if self.mines_shown && cell.mined => CellView::Mine,
+            CellState::Flagged if self.mines_shown && !cell.mined => CellView::WrongFlag,
+            CellState::Hidden => CellView::Hidden,
+            CellState::Flagged => CellView::Flagged,
+            CellState::Questioned => CellView::Questioned,
+            CellState::Exploded => CellView::Exploded,
+            // Treasures hide the number of the cell they are found in.
+            CellState::Revealed if cell.treasure => CellView::Revealed(CellLabel::Treasure),
+            CellState::Revealed => CellView::Revealed(self.number_label(coord, cell.adjacent)),
+        }
+    }
+
+    pub fn toggle_flag_at(&mut self, mut at: Coord, policy: FlagPolicy) -> FlagResult {
+
+        // Coordinates as specified by the user are offset by 1.
+        at.0 -= 1;
+        at.1 -= 1;
+
+        if !(at.0 < self.rows && at.1 < self.cols) {
+            return FlagResult::OutOfBounds;
+        }
+
+        // Do nothing if the parcel has already been explored.
+        // Otherwise, cycle through '.', '>' and '?'.
+
+        let result = match self.cell(at).state {
+            CellState::Revealed | CellState::Exploded => return FlagResult::AlreadyExplored,
+            CellState::Flagged => {
+                debug!("questioned ({}, {})", at.0 + 1, at.1 + 1);
+                FlagResult::Questioned
+            },
+            CellState::Questioned => {
+                debug!("unmarked ({}, {})", at.0 + 1, at.1 + 1);
+                FlagResult::Unflagged
+            },
+            CellState::Hidden => {
+                // Under the strict policy, there cannot be more flags than mines.
+                if policy == FlagPolicy::Strict && self.get_flagged_count() >= self.get_mine_count() {
+                    return FlagResult::LimitReached;
+                }
+
+                if !self.cell(at).mined {
+                    self.wrong_flags += 1;
+                }
+                debug!("flagged ({}, {})", at.0 + 1, at.1 + 1);
+                FlagResult::Flagged
+            },
+        };
+
+        self.cell_mut(at).cycle_mark();
+        result
+    }
+
+    // Internal state of the board, one set of coordinates (offset by 1, as shown) per line.
+    #[cfg(feature = "debug")]
+    pub fn dump(&self) -> String {
+
+        let cells = |keep: fn(&Cell) -> bool| -> String {
+            let coords: Vec<Coord> = self.coords()
+                .filter(|&coord| keep(self.cell(coord)))
+                .map(|(row, col)| (row + 1, col + 1))
+                .collect();
+            format!("{coords:?}")
+        };
+
+        let mut cached: Vec<Coord> = self.cached.iter().map(|&(row, col)| (row + 1, col + 1)).collect();
+        cached.sort_unstable();
+
+        format!("- mines:     {}\n\
+                 - cached:    {cached:?}\n\
+                 - clear:     {}\n\
+                 - flagged:   {}\n\
+                 - marked:    {}\n\
+                 - exploded:  {}\n\
+                 - treasures: {}\n\
+                 - power-ups: {}\n",
+                cells(|cell| cell.mined),
+                cells(Cell::is_explored),
+                cells(Cell::is_flagged),
+                cells(|cell| cell.state == CellState::Questioned),
+                cells(Cell::is_exploded),
+                cells(|cell| cell.treasure),
+                cells(|cell| cell.power_up.is_some()))
+    }
+
+    // Check the invariants of the internal state of the board, and describe the broken ones.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+
+        let mut broken = Vec::new();
+
+        if self.cells.len() != self.rows || self.cells.iter().any(|cells| cells.len() != self.cols) {
+            broken.push(String::from("the cells do not match the dimensions"));
+            return Err(broken);
+        }
+        if !self.cached.iter().all(|&(row, col)| row < self.rows && col < self.cols) {
+            broken.push(String::from("some cached cells are out of bounds"));
+        }
+
+        if self.area != self.rows * self.cols {
+            broken.push(String::from("the area does not match the dimensions"));
+        }
+        if self.get_mine_count() >= self.area {
+            broken.push(String::from("there are no cells left without mines"));
+        }
+
+        let explored: Vec<Coord> = self.coords().filter(|&coord| self.cell(coord).is_explored()).collect();
+        if explored.len() != self.explored {
+            broken.push(String::from("the count of explored cells is wrong"));
+        }
+        if explored.iter().any(|&coord| self.cell(coord).mined) {
+            broken.push(String::from("some explored cells are mined"));
+        }
+        if explored.iter().any(|coord| self.cached.contains(coord)) {
+            broken.push(String::from("some explored cells are cached"));
+        }
+        if self.coords().any(|coord| self.neighbors(coord).into_iter().filter(|&ng| self.cell(ng).mined).count() !=
+                             self.cell(coord).adjacent) {
+            broken.push(String::from("some cells have a wrong count of mined neighbors"));
+        }
+        if self.cells.iter().flatten().any(|cell| cell.is_exploded() && !cell.mined) {
+            broken.push(String::from("some exploded cells are not mined"));
+        }
+        if self.cells.iter().flatten().any(|cell| cell.mined && (cell.treasure || cell.power_up.is_some())) {
+            broken.push(String::from("some treasures or power-ups are hidden in mined cells"));
+        }
+
+        if broken.is_empty() { Ok(()) } else { Err(broken) }
+    }
+
+    pub fn get_lives(&self) -> usize {
+        self.lives
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn get_label_scheme(&self) -> LabelScheme {
+        self.label_scheme
+    }
+
+    pub fn get_rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn get_cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get_mine_count(&self) -> usize {
+        self.cells.iter().flatten().filter(|cell| cell.mined).count()
+    }
+
+    // Whether the first cell explored is still sure to be safe, i.e. the game has not been
+    // restarted with the mines placed by its first move.
+    pub fn is_first_move_safe(&self) -> bool {
+        self.start.is_none()
+    }
+
+    pub fn get_explored_count(&self) -> usize {
+        self.explored
+    }
+
+    pub fn get_flagged_count(&self) -> usize {
+        self.cells.iter().flatten().filter(|cell| cell.is_flagged()).count()
+    }
+
+    pub fn get_wrong_flag_count(&self) -> usize {
+        self.wrong_flags
+    }
+
+    /* Explore the cached cells, and flood fill from those without mined neighbors: their
+     * neighbors are explored in turn, until the whole region is explored with its numbered
+     * border. Safe cells are explored first, so that a mine cached along with them does not go
+     * off before they are explored. Each mine explored then costs a life, if there are any left,
+     * and the game otherwise.
+     */
+    pub fn explore(&mut self) -> Exploration {
+
+        let mut findings = Vec::new();
+
+        let mut queue: Vec<Coord> = self.cached.iter().copied().collect();
+        queue.sort_unstable();
+        let mut queue = VecDeque::from(queue);
+
+        // Mined cells explored, left cached until the safe cells are explored.
+        let mut mined: Vec<Coord> = Vec::new();
+
+        while let Some((row, col)) = queue.pop_front() {
+
+            // Mines are only moved on the first move of the game. Once the board is restarted,
+            // they stay where they were then.
+            let first = self.explored == 0 && self.start.is_none();
+
+            // With a zero opening, the first cell explored has no mined neighbors either.
+            if first && self.zero_opening {
+                self.clear_opening((row, col));
+            }
+
+            // The first cell explored is never mined: a mine found there is moved elsewhere.
+            if first && self.cells[row][col].mined {
+                self.move_mine((row, col));
+            }
+
+            if self.cells[row][col].mined {
+                mined.push((row, col));
+                continue;
+            }
+
+            self.cached.remove(&(row, col));
+            self.explored += 1;
+            self.cells[row][col].reveal();
+
+            let adjacent = self.cells[row][col].adjacent;
+            debug!("explored ({}, {}): {adjacent} mined neighbors", row + 1, col + 1);
+
+            // Without mined neighbors, the neighbors are explored too.
+            if adjacent == 0 {
+                for neighbor in self.neighbors((row, col)) {
+                    if self.cell(neighbor).state != CellState::Revealed && !self.cell(neighbor).is_exploded() &&
+                        self.cached.insert(neighbor) {
+                        queue.push_back(neighbor);
+                    }
+                }
+            }
+
+            if self.cells[row][col].treasure {
+                findings.push(Finding::Treasure);
+            }
+
+            if let Some(power_up) = self.cells[row][col].power_up {
+                findings.push(Finding::PowerUp(power_up));
+            }
+        }
+
+        // The mines are where they will stay once the first cell is explored (and the ones there
+        // or around it moved), which is what restarting the board goes back to.
+        if self.start.is_none() && self.explored > 0 {
+            self.start = Some(self.snapshot());
+        }
+
+        for (row, col) in mined {
+
+            self.cached.remove(&(row, col));
+
+            // If the cell is mined, a life is lost if there are any left. Otherwise, return.
+            if self.lives == 0 {
+                debug!("explored ({}, {}): mined", row + 1, col + 1);
+                self.reveal_mines();
+                return Exploration { findings, result: ExploreResult::Mined };
+            }
+
+            // A life lost cannot be taken back, or undoing would let any cell be probed for free.
+            debug!("explored ({}, {}): mined, {} lives left", row + 1, col + 1, self.lives - 1);
+            self.forget_moves();
+            self.lives -= 1;
+            self.cells[row][col].explode();
+            findings.push(Finding::Exploded(self.lives));
+        }
+
+        // Check if all mines have been found.
+        let result = if self.area - self.explored == self.get_mine_count() {
+            self.flag_mines();
+            ExploreResult::BoardClear
+        }
+        else {
+            ExploreResult::Ok
+        };
+
+        Exploration { findings, result }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_places_the_exact_mine_count() {
+        for (rows, cols) in [(1, 2), (3, 3), (9, 9), (10, 10), (16, 30), (30, 16), (50, 50)] {
+            for mine_count in [0, 1, (rows * cols) / 2, rows * cols - 1] {
+                let board = Board::new(rows, cols, mine_count).unwrap();
+                assert_eq!(board.get_mine_count(), mine_count, "{rows}x{cols} board");
+            }
+        }
+    }
+
+    #[test]
+    fn first_explored_cell_is_never_mined() {
+        for _ in 0..20 {
+            let mut board = Board::new(3, 3, 8).unwrap();
+            board.cache((2, 2));
+            assert!(!matches!(board.explore().result, ExploreResult::Mined));
+            assert_eq!(board.get_mine_count(), 8);
+        }
+    }
+
+    #[test]
+    fn new_rejects_invalid_boards() {
+        assert!(matches!(Board::new(0, 5, 1), Err(BoardError::NullArea)));
+        assert!(matches!(Board::new(3, 3, 9), Err(BoardError::TooManyMines)));
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_board() {
+        let board = Board::new_seeded(16, 30, 99, 42).unwrap();
+        let again = Board::new_seeded(16, 30, 99, 42).unwrap();
+        assert_eq!(board.cells, again.cells);
+        assert_eq!(board.get_seed(), 42);
+    }
+
+    #[test]
+    fn wrong_flags_are_counted_when_placed() {
+        let mut board = Board::with_mines(1, 3, HashSet::from([(0, 0)]), 0);
+        board.toggle_flag_at((1, 1), FlagPolicy::Unlimited);
+        board.toggle_flag_at((1, 3), FlagPolicy::Unlimited);
+        board.toggle_flag_at((1, 3), FlagPolicy::Unlimited);
+        board.toggle_flag_at((1, 3), FlagPolicy::Unlimited);
+        board.cache((1, 3));
+        assert!(matches!(board.explore().result, ExploreResult::BoardClear));
+        assert_eq!(board.get_wrong_flag_count(), 1);
+    }
+
+    #[test]
+    fn chords_explore_around_satisfied_numbers() {
+        let mut board = Board::with_mines(2, 3, HashSet::from([(0, 0)]), 0);
+        board.cache((2, 2));
+        board.explore();
+        assert_eq!(board.get_explored_count(), 1);
+
+        assert!(matches!(board.chord((2, 2)), ChordResult::Unsatisfied));
+        assert!(matches!(board.chord((1, 2)), ChordResult::Unexplored));
+        assert!(matches!(board.chord((3, 1)), ChordResult::InvalidCoordinate));
+
+        board.toggle_flag_at((1, 1), FlagPolicy::Unlimited);
+        assert!(matches!(board.chord((2, 2)), ChordResult::Ok));
+        assert!(matches!(board.explore().result, ExploreResult::BoardClear));
+    }
+
+    #[test]
+    fn treasures_are_hidden_in_safe_cells() {
+        let mut board = Board::new(5, 5, 20).unwrap();
+        board.hide_treasures(10);
+        let treasures: Vec<&Cell> = board.cells.iter().flatten().filter(|cell| cell.treasure).collect();
+        assert_eq!(treasures.len(), 5);
+        assert!(treasures.iter().all(|cell| !cell.mined));
+
+        let mut board = Board::with_mines(1, 3, HashSet::from([(0, 0)]), 0);
+        board.cells[0][2].treasure = true;
+        board.cache((1, 3));
+        assert!(matches!(board.explore().findings[..], [Finding::Treasure]));
+        assert_eq!(board.view((0, 2)), CellView::Revealed(CellLabel::Treasure));
+    }
+
+    #[test]
+    fn radar_scans_a_3x3_area() {
+        let mut board = Board::with_mines(5, 5, HashSet::from([(0, 0)]), 0);
+        assert_eq!(board.radar((2, 2)), Some(true));
+        assert_eq!(board.radar((3, 3)), Some(false));
+        assert_eq!(board.radar((6, 1)), None);
+
+        // The area does not depend on the adjacency rule.
+        board.set_adjacency(Adjacency::Orthogonal);
+        assert_eq!(board.radar((2, 2)), Some(true));
+        board.set_adjacency(Adjacency::Radius(2));
+        assert_eq!(board.radar((3, 3)), Some(false));
+
+        board.set_torus(true);
+        assert_eq!(board.radar((5, 5)), Some(true));
+    }
+
+    #[test]
+    fn defusing_removes_a_mine_next_to_an_explored_cell() {
+        let mut board = Board::with_mines(1, 4, HashSet::from([(0, 0), (0, 2)]), 0);
+        board.cache((1, 4));
+        board.explore();
+
+        assert!(matches!(board.defuse((1, 1)), DefuseResult::NotAdjacent));
+        assert!(matches!(board.defuse((1, 4)), DefuseResult::Clear));
+        assert!(matches!(board.defuse((1, 3)), DefuseResult::Defused));
+        assert_eq!(board.get_mine_count(), 1);
+        board.explore();
+        assert_eq!(board.view((0, 1)), CellView::Revealed(CellLabel::MinedNeighbors(1)));
+    }
+
+    #[test]
+    fn transforms_move_the_cells_with_their_state() {
+        let mut board = Board::with_mines(2, 3, HashSet::from([(0, 0)]), 0);
+        board.cache((2, 3));
+        board.explore();
+        let cells = board.cells.clone();
+
+        board.rotate90();
+        assert_eq!((board.get_rows(), board.get_cols()), (3, 2));
+        assert!(board.cells[0][1].mined);
+        assert!(board.validate().is_ok());
+
+        for _ in 0..3 {
+            board.rotate90();
+        }
+        assert_eq!(board.cells, cells);
+
+        board.mirror_h();
+        assert!(board.cells[0][2].mined);
+        board.mirror_v();
+        assert!(board.cells[1][2].mined);
+        board.mirror_h();
+        board.mirror_v();
+        assert_eq!(board.cells, cells);
+    }
+
+    #[test]
+    fn expanded_boards_keep_their_density_and_progress() {
+        let mut board = Board::new(10, 10, 20).unwrap();
+        board.expand(10, 0);
+        assert_eq!((board.get_rows(), board.get_cols()), (20, 10));
+        assert_eq!(board.get_mine_count(), 40);
+
+        // Explored cells without mined neighbors open into the new area.
+        let mut board = Board::with_mines(3, 3, HashSet::new(), 0);
+        board.cache((1, 1));
+        board.explore();
+        board.expand(1, 1);
+        assert_eq!(board.get_explored_count(), 9);
+        assert!(matches!(board.explore().result, ExploreResult::BoardClear));
+        assert_eq!(board.get_explored_count(), 16);
+    }
+
+    #[test]
+    fn added_mines_leave_a_safe_cell_unexplored() {
+        let mut board = Board::with_mines(1, 6, HashSet::from([(0, 2)]), 0);
+        board.cache((1, 1));
+        board.explore();
+        assert_eq!(board.get_explored_count(), 2);
+
+        assert!(board.add_mine().is_some_and(|(_, col)| col > 2));
+        assert!(board.add_mine().is_some());
+        assert!(board.add_mine().is_none());
+        assert_eq!(board.get_mine_count(), 3);
+        assert!(board.validate().is_ok());
+    }
+
+    #[test]
+    fn cache_reports_cells_that_cannot_be_explored() {
+        let mut board = Board::with_mines(1, 5, HashSet::from([(0, 1)]), 0);
+        board.cache((1, 5));
+        board.explore();
+        board.toggle_flag_at((1, 2), FlagPolicy::Unlimited);
+
+        assert!(matches!(board.cache((1, 6)), CacheResult::InvalidCoordinate));
+        assert!(matches!(board.cache((1, 4)), CacheResult::Clear));
+        assert!(matches!(board.cache((1, 2)), CacheResult::Flagged));
+        assert!(matches!(board.cache((1, 1)), CacheResult::Ok));
+        assert!(matches!(board.cache((1, 1)), CacheResult::AlreadyQueued));
+    }
+
+    #[test]
+    fn strict_policy_limits_flags_to_the_mine_count() {
+        let mut board = Board::with_mines(3, 3, HashSet::from([(0, 0)]), 0);
+        assert!(matches!(board.toggle_flag_at((3, 3), FlagPolicy::Strict), FlagResult::Flagged));
+        assert!(matches!(board.toggle_flag_at((1, 1), FlagPolicy::Strict), FlagResult::LimitReached));
+        assert!(matches!(board.toggle_flag_at((1, 1), FlagPolicy::Unlimited), FlagResult::Flagged));
+        assert_eq!(board.get_flagged_count(), 2);
+    }
+
+    #[test]
+    fn lost_boards_show_mines_and_wrong_flags() {
+        let mut board = Board::with_mines(1, 5, HashSet::from([(0, 0), (0, 2)]), 0);
+        board.cache((1, 4));
+        board.explore();
+        board.toggle_flag_at((1, 1), FlagPolicy::Unlimited);
+        board.toggle_flag_at((1, 2), FlagPolicy::Unlimited);
+
+        board.cache((1, 3));
+        assert!(matches!(board.explore().result, ExploreResult::Mined));
+        assert_eq!(board.get_wrong_flag_count(), 1);
+
+        let views: Vec<CellView> = board.cells().map(|(_, view)| view).collect();
+        assert_eq!(views, vec![CellView::Flagged, CellView::WrongFlag, CellView::Mine,
+                               CellView::Revealed(CellLabel::MinedNeighbors(1)), CellView::Hidden]);
+    }
+
+    #[test]
+    fn flag_results_follow_the_mark_cycle() {
+        let mut board = Board::with_mines(1, 5, HashSet::from([(0, 1)]), 0);
+        board.cache((1, 5));
+        board.explore();
+
+        assert!(matches!(board.toggle_flag_at((1, 1), FlagPolicy::Unlimited), FlagResult::Flagged));
+        assert!(matches!(board.toggle_flag_at((1, 1), FlagPolicy::Unlimited), FlagResult::Questioned));
+        assert!(matches!(board.toggle_flag_at((1, 1), FlagPolicy::Unlimited), FlagResult::Unflagged));
+        assert!(matches!(board.toggle_flag_at((1, 5), FlagPolicy::Unlimited), FlagResult::AlreadyExplored));
+        assert!(matches!(board.toggle_flag_at((2, 1), FlagPolicy::Unlimited), FlagResult::OutOfBounds));
+    }
+
+    #[test]
+    fn cache_many_reports_every_coordinate() {
+        let mut board = Board::with_mines(1, 5, HashSet::from([(0, 1)]), 0);
+        let results = board.cache_many([(1, 1), (1, 9), (1, 1), (1, 5)]);
+        let results: Vec<(Coord, bool)> = results.into_iter()
+            .map(|(coord, result)| (coord, matches!(result, CacheResult::Ok)))
+            .collect();
+        assert_eq!(results, vec![((1, 1), true), ((1, 9), false), ((1, 1), false), ((1, 5), true)]);
+        assert_eq!(board.cached.len(), 2);
+    }
+
+    #[test]
+    fn symmetric_layouts_mirror_their_mines() {
+        let (rows, cols) = (5, 6);
+        let cases = [(Symmetry::Horizontal, 8), (Symmetry::Vertical, 9), (Symmetry::Rot180, 8)];
+
+        for (symmetry, mine_count) in cases {
+            let image = |(row, col): Coord| match symmetry {
+                Symmetry::Horizontal => (row, cols - 1 - col),
+                Symmetry::Vertical => (rows - 1 - row, col),
+                Symmetry::Rot180 => (rows - 1 - row, cols - 1 - col),
+            };
+
+            // Odd counts lose a mine when no cell is its own image.
+            let board = Board::new_symmetric(rows, cols, 9, symmetry, rand::random()).unwrap();
+            assert_eq!(board.get_mine_count(), mine_count);
+            assert!(board.coords().all(|coord| board.cell(coord).mined == board.cell(image(coord)).mined));
+
+            let board = Board::new_symmetric(rows, cols, 10, symmetry, rand::random()).unwrap();
+            assert_eq!(board.get_mine_count(), 10);
+        }
+    }
+
+    #[test]
+    fn expansion_opens_from_every_cell_reaching_the_new_area() {
+        for seed in 0..20 {
+            // The left half of the board is mined, and so is half of the new area.
+            let mines: HashSet<Coord> = (0..6).flat_map(|row| (0..4).map(move |col| (row, col))).collect();
+            let mut board = Board::with_mines(6, 8, mines, seed);
+            board.set_adjacency(Adjacency::Radius(2));
+            board.cache((1, 8));
+            board.explore();
+
+            board.expand(0, 2);
+
+            // Every explored cell without mined neighbors has its new neighbors queued.
+            let open: Vec<Coord> = board.coords()
+                .filter(|&coord| board.cell(coord).is_explored() && board.cell(coord).adjacent == 0)
+                .collect();
+            assert!(open.into_iter()
+                    .flat_map(|coord| board.neighbors(coord))
+                    .all(|ng| board.cell(ng).is_explored() || board.cached.contains(&ng)));
+        }
+    }
+
+    #[test]
+    fn huge_radii_do_not_overflow() {
+        let mut board = Board::new(5, 5, 3).unwrap();
+        board.set_adjacency(Adjacency::Radius(usize::MAX));
+        assert_eq!(board.neighbors((2, 2)).len(), 24);
+        board.set_torus(true);
+        assert_eq!(board.neighbors((2, 2)).len(), 24);
+    }
+
+    #[test]
+    fn orthogonal_neighbors_share_a_side() {
+        let mut board = Board::with_mines(3, 3, HashSet::from([(0, 0)]), 0);
+        board.set_adjacency(Adjacency::Orthogonal);
+        assert_eq!(board.neighbors((0, 0)), vec![(1, 0), (0, 1)]);
+        assert_eq!(board.neighbors((1, 1)).len(), 4);
+
+        // The diagonal neighbor of the mine does not count it.
+        board.cache((2, 2));
+        board.explore();
+        assert_eq!(board.view((1, 1)), CellView::Revealed(CellLabel::Clear));
+        assert_eq!(board.view((0, 1)), CellView::Revealed(CellLabel::MinedNeighbors(1)));
+    }
+
+    #[test]
+    fn liar_numbers_are_off_by_one() {
+        let mut board = Board::with_mines(1, 5, HashSet::from([(0, 3)]), 0);
+        board.make_liar();
+        board.cells[0][2].lies_up = false;
+        board.cells[0][4].lies_up = true;
+        board.cache_many([(1, 1), (1, 5)]);
+        board.explore();
+
+        // Cells without mined neighbors always show 1.
+        let views: Vec<CellView> = board.cells().map(|(_, view)| view).collect();
+        assert_eq!(views, vec![CellView::Revealed(CellLabel::MinedNeighbors(1)),
+                               CellView::Revealed(CellLabel::MinedNeighbors(1)),
+                               CellView::Revealed(CellLabel::MinedNeighbors(0)),
+                               CellView::Flagged,
+                               CellView::Revealed(CellLabel::MinedNeighbors(2))]);
+
+        // Cells added to the board lie too, in both directions.
+        board.expand(10, 10);
+        let new_cells = || board.cells[1..].iter().flatten();
+        assert!(new_cells().any(|cell| cell.lies_up) && new_cells().any(|cell| !cell.lies_up));
+    }
+
+    #[test]
+    fn relocated_mines_move_to_unexplored_cells() {
+        let mut board = Board::with_mines(1, 6, HashSet::from([(0, 2)]), 0);
+        board.cache((1, 1));
+        board.explore();
+
+        assert!(board.relocate_mine());
+        assert_eq!(board.get_mine_count(), 1);
+        assert!(!board.cells[0][2].mined);
+        assert!(board.cells[0][0..2].iter().all(|cell| cell.is_explored() && !cell.mined));
+        assert!(board.validate().is_ok());
+
+        assert!(!Board::with_mines(2, 2, HashSet::new(), 0).relocate_mine());
+    }
+
+    #[cfg(feature = "debug")]
+    #[test]
+    fn dumps_list_the_cells_of_every_kind() {
+        let mut board = Board::with_mines(1, 5, HashSet::from([(0, 1)]), 0);
+        board.cache((1, 5));
+        board.explore();
+        board.toggle_flag_at((1, 1), FlagPolicy::Unlimited);
+        board.toggle_flag_at((1, 2), FlagPolicy::Unlimited);
+        board.toggle_flag_at((1, 2), FlagPolicy::Unlimited);
+
+        assert_eq!(board.dump(), "- mines:     [(1, 2)]\n\
+                                  - cached:    []\n\
+                                  - clear:     [(1, 3), (1, 4), (1, 5)]\n\
+                                  - flagged:   [(1, 1)]\n\
+                                  - marked:    [(1, 2)]\n\
+                                  - exploded:  []\n\
+                                  - treasures: []\n\
+                                  - power-ups: []\n");
+    }
+
+    #[test]
+    fn validation_finds_broken_invariants() {
+        let mut board = Board::with_mines(1, 5, HashSet::from([(0, 1)]), 0);
+        board.cache((1, 5));
+        board.explore();
+        assert_eq!(board.validate(), Ok(()));
+
+        board.cells[0][4].mined = true;
+        board.explored += 1;
+        assert_eq!(board.validate(), Err(vec![String::from("the count of explored cells is wrong"),
+                                              String::from("some explored cells are mined"),
+                                              String::from("some cells have a wrong count of mined neighbors")]));
+    }
+
+    #[test]
+    fn letters_go_on_after_z() {
+        assert_eq!(letters(1), "a");
+        assert_eq!(letters(26), "z");
+        assert_eq!(letters(27), "aa");
+        assert_eq!(letters(52), "az");
+        assert_eq!(letters(703), "aaa");
+
+        assert_eq!(parse_letters("z"), Some(26));
+        assert_eq!(parse_letters("Z"), Some(26));
+        assert_eq!(parse_letters("aa"), Some(27));
+        assert_eq!(parse_letters("AZ"), Some(52));
+        assert_eq!(parse_letters(""), None);
+        assert_eq!(parse_letters("a1"), None);
+        assert_eq!(parse_letters(&"z".repeat(20)), None);
+    }
+
+    #[test]
+    fn labels_are_rewritten_as_numbers() {
+        assert_eq!(LabelScheme::Numbers.to_numbers("3,5", 8), Some(String::from("3,5")));
+        assert_eq!(LabelScheme::LetterColumns.to_numbers("3,e", 8), Some(String::from("3,5")));
+        assert_eq!(LabelScheme::LetterColumns.to_numbers("3,aa", 8), Some(String::from("3,27")));
+        assert_eq!(LabelScheme::LetterColumns.to_numbers("3", 8), Some(String::from("3")));
+        assert_eq!(LabelScheme::LetterColumns.to_numbers(",e", 8), Some(String::from(",5")));
+        assert_eq!(LabelScheme::LetterRows.to_numbers("C,5", 8), Some(String::from("3,5")));
+        assert_eq!(LabelScheme::Chess.to_numbers("e3", 8), Some(String::from("6,5")));
+        assert_eq!(LabelScheme::Chess.to_numbers("", 8), Some(String::new()));
+
+        assert_eq!(LabelScheme::LetterColumns.to_numbers("3,5", 8), None);
+        assert_eq!(LabelScheme::LetterRows.to_numbers("3,5", 8), None);
+        assert_eq!(LabelScheme::Chess.to_numbers("e9", 8), None);
+        assert_eq!(LabelScheme::Chess.to_numbers("3e", 8), None);
+        assert_eq!(LabelScheme::Chess.to_numbers("e", 8), None);
+    }
+
+    #[test]
+    fn expanding_or_transforming_makes_moves_final() {
+        let mut board = Board::with_mines(3, 3, HashSet::from([(0, 0)]), 0);
+        let before = board.snapshot();
+        board.toggle_flag_at((1, 1), FlagPolicy::Unlimited);
+        board.record_move(before);
+
+        board.rotate90();
+        assert!(!board.undo());
+        assert!(board.cells[0][2].is_flagged());
+
+        let before = board.snapshot();
+        board.toggle_flag_at((1, 3), FlagPolicy::Unlimited);
+        board.record_move(before);
+        assert!(board.undo());
+        assert!(board.redo());
+
+        board.expand(1, 0);
+        assert!(!board.undo() && !board.redo());
+        assert_eq!(board.get_rows(), 4);
+    }
+
+    #[test]
+    fn resetting_restores_the_mines_of_the_first_move() {
+        let mut board = Board::with_mines(1, 8, HashSet::from([(0, 0), (0, 7)]), 0);
+        board.cache((1, 1));
+        board.explore();
+
+        let mined = |board: &Board| -> Vec<Coord> {
+            board.coords().filter(|&coord| board.cell(coord).mined).collect()
+        };
+        let mines = mined(&board);
+        assert!(!mines.contains(&(0, 0)));
+
+        board.add_mine();
+        board.expand(1, 1);
+        board.rotate90();
+
+        board.reset();
+        assert_eq!((board.get_rows(), board.get_cols()), (1, 8));
+        assert_eq!(mined(&board), mines);
+        assert_eq!(board.get_explored_count(), 0);
+        assert!(board.cells().all(|(_, view)| view == CellView::Hidden));
+    }
+
+    #[test]
+    fn restarted_boards_keep_their_mines_on_the_first_move() {
+        let mut board = Board::with_mines(1, 8, HashSet::from([(0, 0), (0, 7)]), 0);
+        board.set_zero_opening(true);
+        board.cache((1, 4));
+        board.explore();
+        assert!(board.cell((0, 0)).mined);
+
+        board.reset();
+        assert!(!board.is_first_move_safe());
+        board.cache((1, 1));
+        assert!(matches!(board.explore().result, ExploreResult::Mined));
+        assert!(board.cell((0, 0)).mined && board.cell((0, 7)).mined);
+        assert_eq!(board.get_mine_count(), 2);
+    }
+
+    #[test]
+    fn lives_lost_cannot_be_undone() {
+        let mut board = Board::with_mines(1, 5, HashSet::from([(0, 0), (0, 2)]), 0);
+        board.set_lives(1);
+        board.cache((1, 5));
+        board.explore();
+
+        // Moves are recorded before the cells they cache are explored.
+        let before = board.snapshot();
+        board.cache((1, 1));
+        board.record_move(before);
+        assert!(matches!(board.explore().findings[..], [Finding::Exploded(0)]));
+
+        assert!(!board.undo());
+        assert_eq!(board.get_lives(), 0);
+        assert!(board.cell((0, 0)).is_exploded());
+    }
+
+    #[test]
+    fn liar_boards_cannot_be_chorded() {
+        let mut board = Board::with_mines(1, 5, HashSet::from([(0, 0), (0, 4)]), 0);
+        board.make_liar();
+        board.cache((1, 3));
+        board.explore();
+        board.toggle_flag_at((1, 1), FlagPolicy::Unlimited);
+
+        assert!(matches!(board.chord((1, 2)), ChordResult::LiarBoard));
+        assert!(board.cached.is_empty());
+    }
+
+    #[test]
+    fn boards_cannot_grow_past_the_maximum_area() {
+        let mut board = Board::with_mines(2, 2, HashSet::from([(0, 0)]), 0);
+        assert!(matches!(board.expand(usize::MAX, 0), ExpandResult::TooLarge));
+        assert!(matches!(board.expand(1 << 10, 1 << 10), ExpandResult::TooLarge));
+        assert!(matches!(board.expand(usize::MAX / 2, usize::MAX / 2), ExpandResult::TooLarge));
+        assert_eq!((board.get_rows(), board.get_cols()), (2, 2));
+
+        assert!(matches!(board.expand(1, 0), ExpandResult::Ok));
+        assert_eq!((board.get_rows(), board.get_cols()), (3, 2));
+    }
+
+    #[test]
+    fn defusals_cannot_be_undone() {
+        let mut board = Board::with_mines(1, 5, HashSet::from([(0, 0), (0, 2)]), 0);
+        board.cache((1, 5));
+        board.explore();
+
+        let before = board.snapshot();
+        board.toggle_flag_at((1, 1), FlagPolicy::Unlimited);
+        board.record_move(before);
+
+        assert!(matches!(board.defuse((1, 3)), DefuseResult::Defused));
+        board.explore();
+        assert!(!board.undo());
+        assert!(!board.cell((0, 2)).mined && board.cell((0, 2)).is_explored());
+    }
+
+    #[test]
+    fn cells_show_what_the_player_sees() {
+        let mut board = Board::with_mines(2, 3, HashSet::from([(0, 0)]), 0);
+        board.toggle_flag_at((1, 1), FlagPolicy::Unlimited);
+        board.cache((2, 3));
+        board.explore();
+
+        let views: Vec<(Coord, CellView)> = board.cells().collect();
+        assert_eq!(views, vec![((1, 1), CellView::Flagged),
+                               ((1, 2), CellView::Revealed(CellLabel::MinedNeighbors(1))),
+                               ((1, 3), CellView::Revealed(CellLabel::Clear)),
+                               ((2, 1), CellView::Hidden),
+                               ((2, 2), CellView::Revealed(CellLabel::MinedNeighbors(1))),
+                               ((2, 3), CellView::Revealed(CellLabel::Clear))]);
+    }
+
+    #[test]
+    fn cached_mines_go_off_after_the_safe_cells() {
+        let mut board = Board::with_mines(1, 9, HashSet::from([(0, 0), (0, 4)]), 0);
+        board.cache((1, 8));
+        assert!(matches!(board.explore().result, ExploreResult::Ok));
+        assert_eq!(board.get_explored_count(), 4);
+
+        board.cache_many([(1, 1), (1, 3)]);
+        assert!(matches!(board.explore().result, ExploreResult::Mined));
+        assert_eq!(board.get_explored_count(), 7);
+    }
+
+    #[test]
+    fn mined_neighbor_counts_follow_the_mines() {
+        let mut board = Board::new(9, 9, 10).unwrap();
+        board.cache((5, 5));
+        board.explore();
+        assert!(board.add_mine().is_some());
+        assert!(board.relocate_mine());
+        board.expand(2, 3);
+        board.set_torus(true);
+        board.rotate90();
+        board.set_adjacency(Adjacency::Radius(2));
+        assert!(board.validate().is_ok());
+    }
+
+    #[test]
+    fn restore_goes_back_to_a_snapshot() {
+        let mut board = Board::new(9, 9, 10).unwrap();
+        let start = board.snapshot();
+        let shown = board.to_string();
+
+        board.cache((5, 5));
+        board.explore();
+        board.toggle_flag_at((9, 9), FlagPolicy::Unlimited);
+        board.expand(1, 1);
+
+        board.restore(&start);
+        assert_eq!(board.get_explored_count(), 0);
+        assert_eq!(board.get_flagged_count(), 0);
+        assert_eq!(board.to_string(), shown);
+        assert!(board.validate().is_ok());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn boards_survive_serialization() {
+        let mut board = Board::new(9, 9, 10).unwrap();
+        board.cache((5, 5));
+        board.explore();
+        board.toggle_flag_at((9, 9), FlagPolicy::Unlimited);
+
+        let json = serde_json::to_string(&board).unwrap();
+        let loaded: Board = serde_json::from_str(&json).unwrap();
+        assert!(loaded.validate().is_ok());
+        assert_eq!(loaded.cells, board.cells);
+        assert_eq!(loaded.to_string(), board.to_string());
+    }
+
+    #[test]
+    fn torus_edges_wrap_around() {
+        let mut board = Board::new(5, 5, 0).unwrap();
+        board.set_torus(true);
+        let neighbors = board.neighbors((0, 0));
+        assert_eq!(neighbors.len(), 8);
+        assert!(neighbors.contains(&(4, 4)));
+
+        // Cells reached from both sides are only counted once.
+        let mut small = Board::new(2, 2, 0).unwrap();
+        small.set_torus(true);
+        assert_eq!(small.neighbors((0, 0)), vec![(0, 1), (1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn no_guess_boards_are_solved_by_hints() {
+        for _ in 0..10 {
+            let mut board = Board::new_no_guess(9, 9, 10, Adjacency::Radius(1), false, rand::random()).unwrap();
+            board.explore();
+
+            while let Some(coord) = board.safe_cell() {
+                board.cache(coord);
+                assert!(!matches!(board.explore().result, ExploreResult::Mined));
+            }
+
+            assert_eq!(board.get_explored_count() + board.get_mine_count(), 81);
+        }
+    }
+
+    #[test]
+    fn undo_and_redo_restore_the_board() {
+        let mut board = Board::new(5, 5, 3).unwrap();
+        let before = board.snapshot();
+        board.cache((1, 1));
+        board.explore();
+        board.record_move(before);
+
+        let explored = board.get_explored_count();
+        let after = board.to_string();
+
+        assert!(board.undo());
+        assert_eq!(board.get_explored_count(), 0);
+        assert!(!board.undo());
+
+        assert!(board.redo());
+        assert_eq!(board.get_explored_count(), explored);
+        assert_eq!(board.to_string(), after);
+        assert!(!board.redo());
     }
 }
@@ -0,0 +1,34 @@
+//
+
+use std::io::{self, Write};
+use log::{LevelFilter, Log, Metadata, Record};
+
+// Logger writing to the standard error, so that the trace of what the engine did stays apart
+// from the game itself on the standard output (e.g. with `rmines --log-level debug 2> trace.log').
+struct StderrLogger;
+
+static LOGGER: StderrLogger = StderrLogger;
+
+impl Log for StderrLogger {
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.enabled(record.metadata()) {
+            let _ = writeln!(io::stderr(), "[{:<5} {}] {}", record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {
+        let _ = io::stderr().flush();
+    }
+}
+
+// Send the records of `level' and above to the standard error. Nothing is logged until then.
+pub fn init(level: LevelFilter) {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+}
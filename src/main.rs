@@ -1,11 +1,84 @@
 //
 
+use log::{info, LevelFilter};
 use rand::Rng;
-use std::{io::{self, Write}, time::SystemTime};
+use std::{env, fmt, io::{self, IsTerminal, Write}, process::ExitCode, time::{Duration, SystemTime}};
 
 mod game;
 use game::*;
 
+mod cell;
+
+mod render;
+use render::{BrailleRenderer, Renderer};
+
+mod options;
+use options::*;
+
+mod score;
+use score::ScoreInput;
+
+mod achievements;
+use achievements::{Achievements, GameSummary};
+
+mod blitz;
+use blitz::Blitz;
+
+mod survival;
+use survival::Survival;
+
+mod profiling;
+
+mod logging;
+
+mod status;
+use status::GameState;
+
+mod counters;
+use counters::Counters;
+
+// Errors that end the game before the player quits.
+enum Error {
+    Board(BoardError),
+    Io(io::Error),
+    Usage(String),
+    Inconsistent(Vec<String>),
+}
+
+impl From<BoardError> for Error {
+    fn from(error: BoardError) -> Self {
+        Error::Board(error)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl Error {
+    // Exit status of the program when it stops on the error: 2 if it was given invalid input, and
+    // 4 if something went wrong on its side.
+    fn exit_code(&self) -> u8 {
+        match self {
+            Error::Board(_) | Error::Usage(_) => 2,
+            Error::Io(_) | Error::Inconsistent(_) => 4,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Board(error) => write!(f, "could not create the board: {error}"),
+            Error::Io(error) => write!(f, "input/output error: {error}"),
+            Error::Usage(message) => write!(f, "{message}"),
+            Error::Inconsistent(broken) => write!(f, "inconsistent board state: {}", broken.join(", ")),
+        }
+    }
+}
+
 enum ParseResult<'a> {
     Ok,
     TooManyArguments,
@@ -37,50 +110,456 @@ fn parse_arguments<'a>(line: &'a str, args: &mut [usize], mandatory: bool) -> Pa
     ParseResult::Ok
 } 
 
-fn main() {
+// Coordinates of a cell typed as labeled on the board under `scheme', for a board with `rows'
+// rows. Both values are needed, unless `defaults' are given for the missing ones. Return the
+// reason why they are not valid otherwise.
+fn parse_coords(line: &str, scheme: &LabelScheme, rows: usize,
+                defaults: Option<Coord>) -> Result<Coord, String> {
+
+    let Some(numbers) = scheme.to_numbers(line, rows) else {
+        return Err(format!("'{line}' is not a valid coordinate"));
+    };
+
+    let mut args: [usize; 2] = defaults.map_or([ 0; 2 ], |(row, col)| [ row, col ]);
+
+    match parse_arguments(&numbers, &mut args, defaults.is_none()) {
+        // Values left out at the end of the line are not reported by parse_arguments().
+        ParseResult::Ok if args.contains(&0) => {
+            Err(String::from("invalid number of arguments (expected two)"))
+        },
+        ParseResult::Ok => Ok((args[0], args[1])),
+        ParseResult::TooManyArguments if defaults.is_some() => {
+            Err(String::from("too many arguments, expected two at most: `[row]', `[colum]'"))
+        },
+        ParseResult::MissingArgument | ParseResult::TooManyArguments => {
+            Err(String::from("invalid number of arguments (expected two)"))
+        },
+        ParseResult::InvalidArgument(slice) => Err(format!("'{slice}' is not a valid coordinate")),
+    }
+}
+
+// Apply the rules and hide the special cells enabled in the options in a new board.
+fn prepare_board(board: &mut Board, options: &Options) {
+    board.set_lives(options.lives.unwrap_or_default());
+    board.set_zero_opening(options.opening);
+    board.set_adjacency(options.adjacency_for(board.get_rows(), board.get_cols()));
+    board.set_label_scheme(options.label_scheme);
+    if options.liar {
+        board.make_liar();
+    }
+    if let Some(count) = options.treasures {
+        board.hide_treasures(count);
+    }
+    if let Some(count) = options.power_ups {
+        board.hide_power_ups(count);
+    }
+}
+
+// Why the options rule out boards that can be solved without guessing, if they do: liar numbers
+// cannot be trusted, and treasures hide the numbers of their cells, which the solver relies on.
+fn no_guess_conflict(options: &Options) -> Option<&'static str> {
+    if options.liar {
+        Some("cannot be liar boards")
+    }
+    else if options.treasures.is_some() {
+        Some("cannot hide treasures")
+    }
+    else {
+        None
+    }
+}
+
+// Ring the terminal bell and print a banner with `message' at the end of a game, as enabled
+// in the options. Nothing is printed when the game runs quietly, without a terminal.
+fn announce_end(options: &Options, interactive: bool, message: &str) {
+    if interactive {
+        print!("{}", end_notice(options, message));
+    }
+}
+
+// Bell and banner announcing the end of a game with `message', as printed by announce_end().
+fn end_notice(options: &Options, message: &str) -> String {
+
+    let mut notice = String::new();
+
+    if options.bell {
+        notice.push('\x07');
+    }
+
+    if options.banner {
+        let border = "*".repeat(message.len() + 8);
+        notice.push_str(&format!("{border}\n*   {message}   *\n{border}\n\n"));
+    }
+
+    notice
+}
+
+// Write the status of the game to the status file, if enabled in the options.
+fn export_status(options: &Options, board: &Board, counters: &Counters, state: GameState) {
+
+    let Some(path) = &options.status else {
+        return };
+
+    if let Err(error) = status::write(path, board, counters.get_seconds(), counters.moves, state) {
+        println!(">> Could not write the status file '{path}': {error}\n");
+    }
+}
+
+fn format_time(seconds: u64) -> String {
+    format!("{}h {}m {}s", seconds/3600, (seconds % 3600)/60, ((seconds % 3600) % 60))
+}
+
+// Settings given on the command line.
+struct CommandLine {
+    // Level of the records to log.
+    log_level: LevelFilter,
+
+    // Whether to validate the board after every command.
+    paranoid: bool,
+}
+
+// Parse the command line, i.e. `[--log-level <off|error|warn|info|debug|trace>] [--paranoid]'.
+fn parse_command_line(args: impl IntoIterator<Item = String>) -> Result<CommandLine, Error> {
+
+    let mut command_line = CommandLine { log_level: LevelFilter::Off, paranoid: false };
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+
+        if arg == "--paranoid" {
+            command_line.paranoid = true;
+            continue;
+        }
+
+        let value = match arg.strip_prefix("--log-level") {
+            Some("") => args.next().ok_or_else(|| Error::Usage(String::from("missing log level")))?,
+            Some(rest) if rest.starts_with('=') => rest[1..].to_string(),
+            _ => return Err(Error::Usage(format!("unknown argument '{arg}'"))),
+        };
+        command_line.log_level = value.parse()
+            .map_err(|_| Error::Usage(format!("invalid log level '{value}'")))?;
+    }
+
+    Ok(command_line)
+}
+
+// Boards offered at startup, and by name to the `n' command: name, rows, columns and mine count.
+const PRESETS: &[(&str, usize, usize, usize)] = &[
+    ("beginner", 9, 9, 10),
+    ("intermediate", 16, 16, 40),
+    ("expert", 16, 30, 99),
+];
+
+// Preset picked by its number (starting at 1) in the startup menu.
+fn preset_choice(choice: &str) -> Option<&'static (&'static str, usize, usize, usize)> {
+    choice.parse::<usize>().ok().and_then(|n| PRESETS.get(n.wrapping_sub(1)))
+}
+
+// Read a line from the standard input into `line'. Return false at the end of the input.
+fn read_line(line: &mut String) -> io::Result<bool> {
+    line.clear();
+    Ok(io::stdin().read_line(line)? > 0)
+}
+
+// Ask for the first board of the session: one of the presets, a custom board, or the default
+// board. The default board is also used if the input ends.
+fn startup_menu(prefix: &str) -> Result<Board, Error> {
+
+    println!("Choose the first board:\n");
+    for (number, (name, rows, cols, mines)) in PRESETS.iter().enumerate() {
+        println!("  {}) {name:<12} {rows}x{cols} cells, {mines} mines", number + 1);
+    }
+    println!("  c) custom board\n\n\
+              Or press Enter for a 10x10 board with 50 mines.\n");
+
+    let mut line = String::new();
+
+    loop {
+        print!("{prefix} ");
+        io::stdout().flush()?;
+
+        if !read_line(&mut line)? {
+            return Ok(Board::new(10, 10, 50)?);
+        }
+
+        match line.trim() {
+            "" => return Ok(Board::new(10, 10, 50)?),
+            "c" => return custom_board(prefix),
+            choice => match preset_choice(choice) {
+                Some(&(_, rows, cols, mines)) => return Ok(Board::new(rows, cols, mines)?),
+                None => println!("{prefix} '{choice}' is not one of the choices.\n"),
+            },
+        }
+    }
+}
+
+// Ask whether to play the board just lost again.
+fn ask_retry(prefix: &str) -> io::Result<bool> {
+
+    print!("{prefix} Retry the same board? [y/N] ");
+    io::stdout().flush()?;
+
+    let mut line = String::new();
+    if !read_line(&mut line)? {
+        return Ok(false);
+    }
+    println!();
+
+    Ok(matches!(line.trim(), "y" | "Y" | "yes"))
+}
+
+// Ask for the dimensions and mine count of a custom board, until they are valid.
+fn custom_board(prefix: &str) -> Result<Board, Error> {
+
+    let mut line = String::new();
+    let mut values: [usize; 3] = [ 0; 3 ];
+    let questions = ["Number of rows", "Number of columns", "Number of mines"];
+
+    let mut index = 0;
+    while index < values.len() {
+
+        // Every board needs at least one cell without a mine.
+        let max = match index {
+            2 => values[0].saturating_mul(values[1]) - 1,
+            _ => usize::MAX,
+        };
+
+        match max {
+            usize::MAX => print!("{prefix} {}: ", questions[index]),
+            _ => print!("{prefix} {} (1 to {max}): ", questions[index]),
+        }
+        io::stdout().flush()?;
+
+        if !read_line(&mut line)? {
+            return Ok(Board::new(10, 10, 50)?);
+        }
+
+        match line.trim().parse::<usize>() {
+            Ok(n) if (1..=max).contains(&n) => {
+                values[index] = n;
+                index += 1;
+            },
+            _ => println!("{prefix} '{}' is not a valid number.\n", line.trim()),
+        }
+    }
+
+    Ok(Board::new(values[0], values[1], values[2])?)
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("rmines: {error}. Quitting the game...");
+            ExitCode::from(error.exit_code())
+        }
+    }
+}
+
+fn run() -> Result<ExitCode, Error> {
 
-    // Welcome message.
-    println!("\nWelcome to rmines!\n\
-             A default board of 10x10 cells and approximately 50 mines has been crated.\n\
-             To start a new game with a different board, just type in the command 'n <rows>, <cols>, \
-             <mines>'\nType in 'h' or '?' at the prompt to list all the commands available.\n\
-             Have fun!\n");
+    let command_line = parse_command_line(env::args().skip(1))?;
+    logging::init(command_line.log_level);
 
     let prefix: &'static str = ">>";
-    let mut board = Board::new(10, 10, 50).unwrap();
+
+    // When the input is not a terminal, e.g. when commands are piped in, the game runs quietly:
+    // no welcome message, no prompt, and the board is only printed when asked for or at the end.
+    let interactive = io::stdin().is_terminal();
+
+    // Welcome message. Players at a terminal choose the first board, while scripts get the
+    // default one.
+    let mut board = match interactive {
+        true => {
+            println!("\nWelcome to rmines!\n");
+            let board = startup_menu(prefix)?;
+            println!("To start a new game with a different board, just type in the command 'n <rows>, \
+                      <cols>, <mines>'\nType in 'h' or '?' at the prompt to list all the commands \
+                      available.\nHave fun!\n");
+            board
+        },
+        false => Board::new(10, 10, 50)?,
+    };
+    info!("new game on a {}x{} board with {} mines",
+          board.get_rows(), board.get_cols(), board.get_mine_count());
+
     let mut line = String::new();
     let mut rng = rand::thread_rng();    
-    let mut start_time = SystemTime::now();
+    let mut counters = Counters::default();
+    let mut options = Options::default();
+    let mut achievements = Achievements::load();
+    let mut blitz: Option<Blitz> = None;
+    let mut survival: Option<Survival> = None;
+    let mut deadline: Option<SystemTime> = None;
+    let mut outcome = GameState::Playing;
+    let mut show_board = interactive;
+    let mut restart = false;
     
     'main:
     loop {
 
+        // In paranoid mode, stop at the first inconsistency in the board, whatever the last
+        // command was.
+        if command_line.paranoid {
+            board.validate().map_err(Error::Inconsistent)?;
+        }
+
+        // Play the current board again from the start, as asked with `r' or after losing it.
+        if restart {
+            restart = false;
+            board.reset();
+            board.set_lives(options.lives.unwrap_or_default());
+            println!("{prefix} Restarting the same board.\n");
+            info!("restarted the {}x{} board with {} mines",
+                  board.get_rows(), board.get_cols(), board.get_mine_count());
+            counters.reset();
+            outcome = GameState::Playing;
+            survival = survival.and(options.survival).map(|seconds| Survival::new(seconds, &board));
+            deadline = deadline.and(options.countdown).map(|seconds| counters.start_time + Duration::from_secs(seconds));
+            show_board = interactive;
+        }
+
+        // Sudden death: place the mines due since the start of the game.
+        let seconds_elapsed = counters.get_seconds();
+        let mines_due = options.sudden_time.map_or(0, |seconds| seconds_elapsed / seconds) as usize
+            + options.sudden_moves.map_or(0, |count| counters.moves / count);
+
+        while counters.added_mines < mines_due && board.add_mine().is_some() {
+            counters.added_mines += 1;
+            println!("{prefix} Sudden death! A new mine has been placed on the board.\n");
+        }
+
         // Compute the total playing time.
         let mut playing_time: String =
             String::from("(Could not compute the total playing time.)");
 
-        if let Ok(duration) = start_time.elapsed() {
-            let seconds_elapsed = duration.as_secs();
-            playing_time = format!("{}h {}m {}s",
-                                   seconds_elapsed/3600,
-                                   (seconds_elapsed % 3600)/60,
-                                   ((seconds_elapsed % 3600) % 60));
+        if let Ok(duration) = counters.start_time.elapsed() {
+            playing_time = format_time(duration.as_secs());
         }
 
+        // Count the moves down if a move limit is set.
+        let move_count = match options.move_limit {
+            Some(limit) => format!("Moves left: {}", limit.saturating_sub(counters.moves)),
+            None => format!("Moves: {}", counters.moves),
+        };
+
+        // List the power-ups at hand, if they are in play.
+        let inventory = match options.power_ups {
+            Some(_) => format!("Power-ups: {} radar, {} defuse\n", counters.radars, counters.defusers),
+            None => String::new(),
+        };
+
+        // Show the lives left, if lives are in play.
+        let lives = match options.lives {
+            Some(_) => format!("Lives: {}\n", board.get_lives()),
+            None => String::new(),
+        };
+
+        // Show the time left to clear the board, if a countdown is running.
+        let time_left = match deadline {
+            Some(deadline) => format!("Time left: {}\n", format_time(deadline
+                .duration_since(SystemTime::now())
+                .map_or(0, |duration| duration.as_secs()))),
+            None => String::new(),
+        };
+
+        // Point out boards whose edges wrap around.
+        let geometry = match board.is_torus() {
+            true => "Torus: the edges wrap around\n",
+            false => "",
+        };
+
+        // Show the blitz countdown, if a blitz run is going on.
+        let countdown = match &blitz {
+            Some(blitz) => format!("Blitz: {}s left, {} boards cleared\n",
+                                   blitz.get_seconds_left(), blitz.get_cleared()),
+            None => String::new(),
+        };
+
+        // Show the state of the current wave, if a survival run is going on.
+        let wave = match &survival {
+            Some(survival) => format!("Survival: wave {}, {}s left, {} cells to explore\n",
+                                      survival.get_waves() + 1, survival.get_seconds_left(),
+                                      survival.get_cells_left(&board)),
+            None => String::new(),
+        };
+
+        export_status(&options, &board, &counters, GameState::Playing);
+
         // Print the board and other information related to the current game.
-        print!("{}\n\
-                Flagged {flagged} cells of {mine_count} mined\n\
-                Total playing time: {playing_time}\n\n\
-                {prefix} ",
-               board, flagged = board.get_flagged_count(),
-               mine_count = board.get_mine_count());
-            io::stdout().flush().unwrap();
+        if show_board {
+            profiling::measure("rendering", || print!("{}\n\
+                    Flagged {flagged} cells of {mine_count} mined\n\
+                    {move_count}\n\
+                    {time_left}\
+                    {geometry}\
+                    {lives}\
+                    {inventory}\
+                    {countdown}\
+                    {wave}\
+                    Total playing time: {playing_time}\n\n",
+                   board, flagged = board.get_flagged_count(),
+                   mine_count = board.get_mine_count()));
+        }
+        show_board = interactive;
+
+        if interactive {
+            print!("{prefix} ");
+        }
+        io::stdout().flush()?;
         
         line.clear();
         match io::stdin().read_line(&mut line) {
 
+            // The end of the input quits the game, as 'q' does.
+            Ok(0) => {
+                info!("quit");
+                break 'main;
+            },
+
             Ok(_) => {
 
+                // The game is lost as soon as the countdown runs out.
+                if deadline.is_some_and(|deadline| SystemTime::now() >= deadline) {
+                    board.reveal_mines();
+                    println!("{prefix} Time's up! The board was not cleared in time.\n\n\
+                              {board}\n\
+                              Game over!\n");
+                    info!("game lost: countdown over");
+                    outcome = GameState::TimeUp;
+                    export_status(&options, &board, &counters, outcome);
+                    announce_end(&options, interactive, "TIME'S UP!");
+                    break 'main;
+                }
+
+                // A blitz run is over as soon as its time is up.
+                if let Some(blitz) = blitz.as_ref().filter(|blitz| blitz.is_over()) {
+                    println!("{prefix} Time's up! You cleared {} boards.\n", blitz.get_cleared());
+                    info!("blitz run over");
+                    outcome = GameState::Over;
+                    export_status(&options, &board, &counters, outcome);
+                    announce_end(&options, interactive, "TIME'S UP!");
+                    break 'main;
+                }
+
+                // A survival wave is over as soon as its time is up. The run is lost if the quota
+                // was not met, and goes on with more mines otherwise.
+                if let Some(survival) = survival.as_mut().filter(|survival| survival.is_wave_over()) {
+                    if survival.get_cells_left(&board) > 0 {
+                        println!("{prefix} The wave is over and the quota was not met! \
+                                  You survived {} waves.\n", survival.get_waves());
+                        info!("survival quota missed");
+                        outcome = GameState::Lost;
+                        export_status(&options, &board, &counters, outcome);
+                        announce_end(&options, interactive, "GAME OVER");
+                        break 'main;
+                    }
+
+                    let added = survival.next_wave(&mut board);
+                    println!("{prefix} Wave {} survived! {added} new mines have been placed \
+                              on the board.\n", survival.get_waves());
+                }
+
                 // Eat up all whitespace before processing the input line.
                 line.retain(|c| !c.is_whitespace());
 
@@ -90,23 +569,64 @@ fn main() {
                     // any missing argument is replaced by a random value chosen appropriately. All
                     // arguments must be convertible to `usize'.
 
-                    let arg_line = line.strip_prefix(cmd).unwrap();
+                    let arg_line = &line[cmd.len_utf8()..];
+
+                    // Whether the command left cells to explore in the cache.
+                    let mut explore = false;
+
+                    // State of the board before a move, kept if the move is made so that it can be
+                    // taken back.
+                    let moves_before = counters.moves;
+                    let mut before = matches!(cmd, 'x' | 'c' | 'H' | 'f' | '>').then(|| board.snapshot());
 
                     match cmd {
                         'n' => { // Start a new game.
 
-                            let mut args: [usize; 3] = [ 0, 0, 0 ];
-                            match parse_arguments(arg_line, &mut args, false) {
-                                ParseResult::TooManyArguments => {
-                                    println!("{prefix} '{cmd}': too many arguments, expected three \
-                                              at most: `[rows]', `[columns]', and `[mine count]'.\n");
-                                    continue;
+                            // `n!' starts a game on a board that can be solved without guessing,
+                            // and `n~' on a torus. Both can be combined.
+                            let mut arg_line = arg_line;
+                            let mut no_guess = false;
+                            let mut torus = false;
+
+                            loop {
+                                match arg_line.chars().next() {
+                                    Some('!') => no_guess = true,
+                                    Some('~') => torus = true,
+                                    _ => break,
+                                }
+                                arg_line = &arg_line[1..];
+                            }
+
+                            if let Some(reason) = no_guess.then(|| no_guess_conflict(&options)).flatten() {
+                                println!("{prefix} '{cmd}': boards without guessing {reason}.\n");
+                                continue;
+                            }
+
+                            let mut args: [usize; 4] = [ 0, 0, 0, 0 ];
+
+                            // Boards can also be given by the name of a preset.
+                            if let Some(&(_, rows, cols, mines)) = PRESETS.iter().find(|(name, ..)| *name == arg_line) {
+                                args = [ rows, cols, mines, 0 ];
+                            }
+                            else if arg_line.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                                let names: Vec<&str> = PRESETS.iter().map(|(name, ..)| *name).collect();
+                                println!("{prefix} '{cmd}': unknown preset '{arg_line}', expected one of: {}.\n",
+                                         names.join(", "));
+                                continue;
+                            }
+                            else {
+                                match parse_arguments(arg_line, &mut args, false) {
+                                    ParseResult::TooManyArguments => {
+                                        println!("{prefix} '{cmd}': too many arguments, expected four \
+                                                  at most: `[rows]', `[columns]', `[mine count]', and `[seed]'.\n");
+                                        continue;
+                                    }
+                                    ParseResult::InvalidArgument(slice) => {
+                                        println!("{prefix} '{cmd}':  '{slice}' is not a valid coordinate.\n");
+                                        continue;
+                                    },
+                                    _ => {}
                                 }
-                                ParseResult::InvalidArgument(slice) => {
-                                    println!("{prefix} '{cmd}':  '{slice}' is not a valid coordinate.\n");
-                                    continue;
-                                },
-                                _ => {}
                             }
 
                             // Fill in any missing arguments with defaults.
@@ -114,108 +634,577 @@ fn main() {
                             if args[1] == 0 { args[1] = rng.gen_range(1..=10); }
                             if args[2] == 0 { args[2] = rng.gen_range(1..=(args[0] * args[1])); }
 
+                            // The same seed always gives the same board.
+                            let seed = match args[3] {
+                                0 => rng.gen(),
+                                seed => seed as u64,
+                            };
+
                             // Try to create a new board.
-                            match Board::new(args[0], args[1], args[2]) {
-                                Ok(new_board) => {
+                            let new_board = profiling::measure("generation", || match options.symmetry {
+                                _ if no_guess => Board::new_no_guess(args[0], args[1], args[2],
+                                                                 options.adjacency_for(args[0], args[1]), torus, seed),
+                                Some(symmetry) => Board::new_symmetric(args[0], args[1], args[2], symmetry, seed),
+                                None => Board::new_seeded(args[0], args[1], args[2], seed),
+                            });
+
+                            match new_board {
+                                Ok(mut new_board) => {
                                     println!("{prefix} Starting a new game. The new board has {rows} rows, \
-                                              {cols} columns, and (approximately) {count} mines.\n",
-                                             rows = args[0], cols = args[1], count = args[2]);
+                                              {cols} columns, and {count} mines (seed {seed}).\n",
+                                             rows = new_board.get_rows(), cols = new_board.get_cols(),
+                                             count = new_board.get_mine_count(), seed = new_board.get_seed());
+                                    new_board.set_torus(torus);
+                                    prepare_board(&mut new_board, &options);
                                     board = new_board;
-                                    start_time = SystemTime::now();
+
+                                    // The opening of boards without guessing is explored right away.
+                                    if no_guess {
+                                        println!("{prefix} This board can be solved without guessing, \
+                                                  starting from its opening.\n");
+                                        explore = true;
+                                    }
+
+                                    info!("new game on a {}x{} board with {} mines",
+                                          board.get_rows(), board.get_cols(), board.get_mine_count());
+                                    counters.reset();
+
+                                    // Start a blitz run with boards like this one, if enabled.
+                                    blitz = options.blitz.map(|seconds| Blitz::new(seconds, args[0], args[1], args[2]));
+                                    if let Some(seconds) = options.blitz {
+                                        println!("{prefix} Blitz! Clear as many boards as you can in {seconds}s.\n");
+                                    }
+
+                                    // Start the countdown, unless the board is part of a blitz run.
+                                    deadline = options.countdown
+                                        .filter(|_| blitz.is_none())
+                                        .map(|seconds| counters.start_time + Duration::from_secs(seconds));
+                                    if let Some(seconds) = options.countdown.filter(|_| blitz.is_none()) {
+                                        println!("{prefix} Countdown! Clear the board within {seconds}s.\n");
+                                    }
+
+                                    // Start a survival run on this board, unless it is part of a blitz run.
+                                    survival = options.survival
+                                        .filter(|_| blitz.is_none())
+                                        .map(|seconds| Survival::new(seconds, &board));
+                                    if let Some(survival) = &survival {
+                                        println!("{prefix} Survival! Explore {} cells every {}s to keep \
+                                                  the waves of mines at bay.\n",
+                                                 survival.get_cells_left(&board), options.survival.unwrap_or_default());
+                                    }
                                 },
                                 Err(BoardError::NullArea) => {
                                     println!("{prefix} '{cmd}': Cannot create a board with zero rows or columns!\n");
                                 },
                                 Err(BoardError::TooManyMines) => {
                                     println!("{prefix} '{cmd}': Too many mines for such a small board!\n");
+                                },
+                                Err(BoardError::NoGuessNotFound) => {
+                                    println!("{prefix} '{cmd}': Could not find a board that can be solved \
+                                              without guessing. Try fewer mines.\n");
                                 }
                             }
                         },
 
+                        'x' if arg_line.contains(';') => { // Explore several cells at once.
+
+                            let mut coords: Vec<Coord> = Vec::new();
+
+                            for pair in arg_line.split(';') {
+                                match parse_coords(pair, &board.get_label_scheme(), board.get_rows(), None) {
+                                    Ok(coord) => coords.push(coord),
+                                    Err(error) => {
+                                        println!("{prefix} '{cmd}': {error}.\n");
+                                        continue 'main;
+                                    },
+                                }
+                            }
+
+                            let mut queued = false;
+
+                            for (coord, result) in board.cache_many(coords) {
+                                let cell = board.cell_label(coord);
+                                match result {
+                                    CacheResult::Ok => queued = true,
+                                    CacheResult::InvalidCoordinate => {
+                                        println!("{prefix} '{cmd}': invalid cell coordinate {cell}.");
+                                    },
+                                    CacheResult::Clear => {
+                                        println!("{prefix} '{cmd}': the cell at {cell} is clear.");
+                                    },
+                                    CacheResult::Flagged => {
+                                        println!("{prefix} '{cmd}': the cell at {cell} is flagged.");
+                                    },
+                                    CacheResult::AlreadyQueued => {
+                                        println!("{prefix} '{cmd}': the cell at {cell} is given twice.");
+                                    },
+                                    CacheResult::Exploded => {
+                                        println!("{prefix} '{cmd}': the mine at {cell} already went off.");
+                                    },
+                                }
+                            }
+
+                            if !queued {
+                                println!();
+                                continue;
+                            }
+
+                            counters.moves += 1;
+                            explore = true;
+                        },
+
                         'x' => { // Explore the cell at the given coordinate.
                             
                             // Randomly choose a cell to explore if the user doesn't provide any.
-                            let mut args: [usize; 2] = [
+                            let random = (
                                 rng.gen_range(1..=board.get_rows()), // Row
                                 rng.gen_range(1..=board.get_cols()), // Column
-                            ];
+                            );
 
-                            // Parse arguments.
-                            match parse_arguments(arg_line, &mut args, false) {
-                                ParseResult::TooManyArguments => {
-                                    println!("{prefix} '{cmd}': too many arguments, expected \
-                                              two at most: `[row]', `[colum]'.\n");
+                            let coord = match parse_coords(arg_line, &board.get_label_scheme(),
+                                                           board.get_rows(), Some(random)) {
+                                Ok(coord) => coord,
+                                Err(error) => {
+                                    println!("{prefix} '{cmd}': {error}.\n");
                                     continue;
                                 },
-                                ParseResult::InvalidArgument(slice) => {
-                                    println!("{prefix} '{cmd}': '{slice}' is not a valid coordinate.\n");
-                                    continue;
-                                },
-                                _ => {}
-                            }
+                            };
                             
                             // Try to add the specified coordinate to the unexplored cache.
-                            match board.cache((args[0], args[1])) {
+                            match board.cache(coord) {
                                 CacheResult::InvalidCoordinate => {
-                                    println!("{prefix} '{cmd}': invalid cell coordinate ({x}, {y}).\n",
-                                             x = args[0], y = args[1]);
+                                    println!("{prefix} '{cmd}': invalid cell coordinate {cell}.\n",
+                                             cell = board.cell_label(coord));
                                     continue 'main;
                                 },
+                                CacheResult::Clear if options.chord => {
+
+                                    // Re-exploring a clear cell explores its unflagged neighbors
+                                    // if the flags around it account for all its mined neighbors.
+                                    match board.chord(coord) {
+                                        ChordResult::Ok => {},
+                                        ChordResult::Unsatisfied => {
+                                            println!("{prefix} '{cmd}': the flags around {cell} do \
+                                                      not match its number.\n",
+                                                     cell = board.cell_label(coord));
+                                            continue 'main;
+                                        },
+                                        ChordResult::LiarBoard => {
+                                            println!("{prefix} '{cmd}': there is no chording on liar boards.\n");
+                                            continue 'main;
+                                        },
+                                        ChordResult::InvalidCoordinate | ChordResult::Unexplored => {
+                                            unreachable!("the cell was reported as clear")
+                                        },
+                                    }
+                                },
                                 CacheResult::Clear => {
-                                    println!("{prefix} '{cmd}': the cell at ({x}, {y}) is clear.\n",
-                                             x = args[0], y = args[1]);
+                                    println!("{prefix} '{cmd}': the cell at {cell} is clear.\n",
+                                             cell = board.cell_label(coord));
                                     continue 'main;
                                 },
-                                CacheResult::Ok => {
-
-                                    loop {
-
-                                        // Explores the chosen cell and its neighborhood 
-                                        // until a mined neighbor is found.
-
-                                        match board.explore() {
-                                            ExploreResult::Ok => {}, // Added for readability.
-                                            ExploreResult::BoardClear => {
-                                                println!("{prefix} Congratulations! All mines have \
-                                                          been found!\n\n\
-                                                          {board}\n");
-                                                break 'main;
-                                            },
-                                            ExploreResult::EmptyCache => break,
-                                            ExploreResult::Mined => {
-                                                println!("{prefix} The cell is mined!\n\n\
-                                                          {board}\n\
-                                                          Game over!\n");
-                                                // TODO: ask the user if they want to start a new game.
-                                                break 'main;
-                                            },
-                                        }
-                                    }
-                                }
+                                CacheResult::Flagged => {
+                                    println!("{prefix} '{cmd}': the cell at {cell} is flagged. \
+                                              Unflag it first to explore it.\n",
+                                             cell = board.cell_label(coord));
+                                    continue 'main;
+                                },
+                                CacheResult::AlreadyQueued => {
+                                    println!("{prefix} '{cmd}': the cell at {cell} is already \
+                                              queued for exploration.\n",
+                                             cell = board.cell_label(coord));
+                                    continue 'main;
+                                },
+                                CacheResult::Exploded => {
+                                    println!("{prefix} '{cmd}': the mine at {cell} already went off.\n",
+                                             cell = board.cell_label(coord));
+                                    continue 'main;
+                                },
+                                CacheResult::Ok => {},
                             }
+
+                            counters.moves += 1;
+                            explore = true;
+                        },
+
+                        'c' => { // Explore the unflagged neighbors of a cell whose number is satisfied.
+
+                            let coord = match parse_coords(arg_line, &board.get_label_scheme(), board.get_rows(), None) {
+                                Ok(coord) => coord,
+                                Err(error) => {
+                                    println!("{prefix} '{cmd}': {error}.\n");
+                                    continue;
+                                },
+                            };
+
+                            match board.chord(coord) {
+                                ChordResult::Ok => {},
+                                ChordResult::InvalidCoordinate => {
+                                    println!("{prefix} '{cmd}': invalid cell coordinate {cell}.\n",
+                                             cell = board.cell_label(coord));
+                                    continue;
+                                },
+                                ChordResult::Unexplored => {
+                                    println!("{prefix} '{cmd}': the cell at {cell} has not been explored yet.\n",
+                                             cell = board.cell_label(coord));
+                                    continue;
+                                },
+                                ChordResult::Unsatisfied => {
+                                    println!("{prefix} '{cmd}': the flags around {cell} do not match its number.\n",
+                                             cell = board.cell_label(coord));
+                                    continue;
+                                },
+                                ChordResult::LiarBoard => {
+                                    println!("{prefix} '{cmd}': there is no chording on liar boards.\n");
+                                    continue;
+                                },
+                            }
+
+                            counters.moves += 1;
+                            explore = true;
+                        },
+
+                        'H' => { // Explore a cell that is provably safe.
+
+                            if !arg_line.is_empty() {
+                                println!("{prefix} '{cmd}': unknown command. Did you mean 'H'?\n");
+                                continue;
+                            }
+
+                            if board.is_liar() {
+                                println!("{prefix} '{cmd}': there are no hints on liar boards.\n");
+                                continue;
+                            }
+
+                            if board.get_explored_count() == 0 && board.is_first_move_safe() {
+                                println!("{prefix} '{cmd}': the first cell explored is always safe.\n");
+                                continue;
+                            }
+
+                            let Some(coord) = board.safe_cell() else {
+                                println!("{prefix} '{cmd}': no cell is provably safe, you will have to guess.\n");
+                                continue;
+                            };
+
+                            println!("{prefix} The cell at {cell} is safe.\n", cell = board.cell_label(coord));
+                            board.cache(coord);
+                            counters.moves += 1;
+                            explore = true;
                         },
 
                         'f' | '>' => { // Flag the cell at the coordinate given.
 
+                            let coord = match parse_coords(arg_line, &board.get_label_scheme(), board.get_rows(), None) {
+                                Ok(coord) => coord,
+                                Err(error) => {
+                                    println!("{prefix} '{cmd}': {error}.\n");
+                                    continue;
+                                },
+                            };
+
+                            let wrong_flags = board.get_wrong_flag_count();
+
+                            match board.toggle_flag_at(coord, options.flag_policy) {
+                                FlagResult::Flagged | FlagResult::Unflagged | FlagResult::Questioned => {},
+                                FlagResult::AlreadyExplored => {
+                                    println!("{prefix} '{cmd}': the cell at {cell} has already been \
+                                              explored.\n",
+                                             cell = board.cell_label(coord));
+                                    continue 'main;
+                                },
+                                FlagResult::OutOfBounds => {
+                                    println!("{prefix} '{cmd}': invalid cell coordinate {cell}.\n",
+                                             cell = board.cell_label(coord));
+                                    continue 'main;
+                                },
+                                FlagResult::LimitReached => {
+                                    println!("{prefix} '{cmd}': there are already as many flags as mines. \
+                                              Remove a flag first, or set 'flags=unlimited'.\n");
+                                    continue 'main;
+                                },
+                            }
+
+                            // Under the relocating mines rule, every wrong flag silently moves a mine.
+                            // Explored cells left without mined neighbors get their neighbors explored.
+                            // Moving a mine makes the moves so far final.
+                            if options.relocate && board.get_wrong_flag_count() > wrong_flags {
+                                before = None;
+                                explore = board.relocate_mine();
+                            }
+
+                            counters.moves += 1;
+                            counters.used_flags = true;
+                        },
+
+                        'R' => { // Use a radar on the area centered at the given coordinate.
+
+                            let coord = match parse_coords(arg_line, &board.get_label_scheme(), board.get_rows(), None) {
+                                Ok(coord) => coord,
+                                Err(error) => {
+                                    println!("{prefix} '{cmd}': {error}.\n");
+                                    continue;
+                                },
+                            };
+
+                            if counters.radars == 0 {
+                                println!("{prefix} '{cmd}': you have no radar power-ups.\n");
+                                continue;
+                            }
+
+                            match board.radar(coord) {
+                                Some(true) => {
+                                    println!("{prefix} The radar detects mines around {cell}.\n",
+                                             cell = board.cell_label(coord));
+                                },
+                                Some(false) => {
+                                    println!("{prefix} The radar detects no mines around {cell}.\n",
+                                             cell = board.cell_label(coord));
+                                },
+                                None => {
+                                    println!("{prefix} '{cmd}': invalid cell coordinate {cell}.\n",
+                                             cell = board.cell_label(coord));
+                                    continue;
+                                },
+                            }
+
+                            counters.radars -= 1;
+                        },
+
+                        'D' => { // Defuse the mine (if any) at the given coordinate.
+
+                            let coord = match parse_coords(arg_line, &board.get_label_scheme(), board.get_rows(), None) {
+                                Ok(coord) => coord,
+                                Err(error) => {
+                                    println!("{prefix} '{cmd}': {error}.\n");
+                                    continue;
+                                },
+                            };
+
+                            if counters.defusers == 0 {
+                                println!("{prefix} '{cmd}': you have no defuse power-ups.\n");
+                                continue;
+                            }
+
+                            match board.defuse(coord) {
+                                DefuseResult::Defused => {
+                                    println!("{prefix} The mine at {cell} has been defused.\n",
+                                             cell = board.cell_label(coord));
+                                },
+                                DefuseResult::NotMined => {
+                                    println!("{prefix} There was no mine at {cell}.\n",
+                                             cell = board.cell_label(coord));
+                                },
+                                DefuseResult::InvalidCoordinate => {
+                                    println!("{prefix} '{cmd}': invalid cell coordinate {cell}.\n",
+                                             cell = board.cell_label(coord));
+                                    continue;
+                                },
+                                DefuseResult::Clear => {
+                                    println!("{prefix} '{cmd}': the cell at {cell} is clear.\n",
+                                             cell = board.cell_label(coord));
+                                    continue;
+                                },
+                                DefuseResult::NotAdjacent => {
+                                    println!("{prefix} '{cmd}': the cell at {cell} is not next to an \
+                                              explored cell.\n",
+                                             cell = board.cell_label(coord));
+                                    continue;
+                                },
+                            }
+
+                            counters.defusers -= 1;
+                            counters.moves += 1;
+                            explore = true;
+                        },
+
+                        'e' => { // Expand the board with the given number of rows and columns.
+
                             let mut args: [usize; 2] = [ 0; 2 ];
 
-                            match parse_arguments(arg_line, &mut args, true) {
-                                ParseResult::MissingArgument | ParseResult::TooManyArguments => {
-                                    println!("{prefix} '{cmd}': invalid number of arguments (expected two).\n");
+                            match parse_arguments(arg_line, &mut args, false) {
+                                ParseResult::TooManyArguments => {
+                                    println!("{prefix} '{cmd}': too many arguments, expected \
+                                              two at most: `[rows]', `[columns]'.\n");
                                     continue;
                                 },
                                 ParseResult::InvalidArgument(slice) => {
-                                    println!("{prefix} '{cmd}': '{slice}' is not a valid coordinate.\n");
+                                    println!("{prefix} '{cmd}': '{slice}' is not a valid number.\n");
                                     continue;
                                 },
                                 _ => {}
                             }
 
-                            if !board.update_label((args[0], args[1]), CellLabel::Flag, true) {
-                                println!("{prefix} '{cmd}': invalid cell coordinate ({x}, {y}).\n",
-                                         x = args[0], y = args[1]);
-                                continue 'main;
+                            if args == [0, 0] {
+                                println!("{prefix} '{cmd}': expected the number of rows or columns to add.\n");
+                                continue;
+                            }
+
+                            match board.expand(args[0], args[1]) {
+                                ExpandResult::Ok => {
+                                    println!("{prefix} Added {rows} rows and {cols} columns to the board.\n",
+                                             rows = args[0], cols = args[1]);
+                                },
+                                ExpandResult::TooLarge => {
+                                    println!("{prefix} '{cmd}': boards cannot have more than \
+                                              {MAX_EXPANDED_AREA} cells.\n");
+                                    continue;
+                                },
+                            }
+
+                            // Explored cells without mined neighbors open into the new area.
+                            explore = true;
+                        },
+
+                        't' => { // Rotate or mirror the board.
+
+                            match arg_line {
+                                "r" => board.rotate90(),
+                                "h" => board.mirror_h(),
+                                "v" => board.mirror_v(),
+                                "" => {
+                                    println!("{prefix} '{cmd}': missing argument, expected one of \
+                                              'r' (rotate), 'h' (mirror horizontally), or 'v' (mirror vertically).\n");
+                                    continue;
+                                },
+                                _ => {
+                                    println!("{prefix} '{cmd}': '{arg_line}' is not a valid transform.\n");
+                                    continue;
+                                },
+                            }
+                        },
+
+                        'r' => { // Restart the current board.
+
+                            if !arg_line.is_empty() {
+                                println!("{prefix} '{cmd}': unknown command. Did you mean 'r'?\n");
+                                continue;
+                            }
+
+                            if blitz.is_some() {
+                                println!("{prefix} '{cmd}': boards of a blitz run cannot be restarted.\n");
+                                continue;
+                            }
+
+                            restart = true;
+                            continue;
+                        },
+
+                        'u' | 'U' => { // Take back the last move, or make again the last move taken back.
+
+                            if !arg_line.is_empty() {
+                                println!("{prefix} '{cmd}': unknown command. Did you mean '{cmd}'?\n");
+                                continue;
+                            }
+
+                            if cmd == 'u' && !board.undo() {
+                                println!("{prefix} '{cmd}': there is no move to undo.\n");
+                                continue;
+                            }
+
+                            if cmd == 'U' && !board.redo() {
+                                println!("{prefix} '{cmd}': there is no move to redo.\n");
+                                continue;
+                            }
+                        },
+
+                        'b' => { // Show a braille overview of the board.
+
+                            if !arg_line.is_empty() {
+                                println!("{prefix} '{cmd}': unknown command. Did you mean 'b'?\n");
+                                continue;
+                            }
+
+                            print!("\n{}\n", BrailleRenderer.render(&board));
+                            continue;
+                        },
+
+                        'p' => { // Print the board, e.g. when commands are piped in.
+
+                            if !arg_line.is_empty() {
+                                println!("{prefix} '{cmd}': unknown command. Did you mean 'p'?\n");
+                                continue;
+                            }
+
+                            show_board = true;
+                            continue;
+                        },
+
+                        'a' => { // List the achievements.
+
+                            if !arg_line.is_empty() {
+                                println!("{prefix} '{cmd}': unknown command. Did you mean 'a'?\n");
+                                continue;
+                            }
+
+                            print!("\nAchievements:\n\n{achievements}\n");
+                            continue;
+                        },
+
+                        'P' => { // Show where the time went, in builds with profiling.
+
+                            if !arg_line.is_empty() {
+                                println!("{prefix} '{cmd}': unknown command. Did you mean 'P'?\n");
+                                continue;
+                            }
+
+                            match profiling::report() {
+                                Some(report) => print!("\nTime spent so far:\n\n{report}\n"),
+                                None => println!("{prefix} '{cmd}': rmines was built without the 'profiling' \
+                                                  feature.\n"),
                             }
+                            continue;
+                        },
+
+                        'd' => { // Dump the internal state of the board, in builds with debugging.
+
+                            if !arg_line.is_empty() {
+                                println!("{prefix} '{cmd}': unknown command. Did you mean 'd'?\n");
+                                continue;
+                            }
+
+                            #[cfg(feature = "debug")]
+                            {
+                                print!("\nBoard state:\n\n{}\n", board.dump());
+                                match board.validate() {
+                                    Ok(()) => println!("All invariants hold.\n"),
+                                    Err(broken) => println!("Broken invariants:\n\n- {}\n", broken.join("\n- ")),
+                                }
+                            }
+
+                            #[cfg(not(feature = "debug"))]
+                            println!("{prefix} '{cmd}': rmines was built without the 'debug' feature.\n");
+
+                            continue;
+                        },
+
+                        'o' => { // Show or change the game options.
+
+                            if arg_line.is_empty() {
+                                print!("\nOptions:\n\n{options}\n");
+                                continue;
+                            }
+
+                            let (name, value) = match arg_line.split_once('=') {
+                                Some((name, value)) => (name, Some(value)),
+                                None => (arg_line, None),
+                            };
+
+                            match options.set(name, value) {
+                                Ok(()) => {
+                                    // Labels change on the current board as well.
+                                    if name == "labels" {
+                                        board.set_label_scheme(options.label_scheme);
+                                    }
+                                    println!("{prefix} Option '{name}' updated.\n");
+                                },
+                                Err(OptionError::UnknownOption(name)) => {
+                                    println!("{prefix} '{cmd}': unknown option '{name}'.\n");
+                                },
+                                Err(OptionError::InvalidValue(value)) => {
+                                    println!("{prefix} '{cmd}': '{value}' is not a valid value for '{name}'.\n");
+                                },
+                            }
+                            continue;
                         },
 
                         'h' | '?' =>  { // Print the list of available commands.
@@ -226,13 +1215,63 @@ fn main() {
                             }
                             
                             println!("\nAvailable commands:\n\n\
-                                      - n   rows, columns, mines  start a new game with the given board dimensions and mines.\n\
+                                      - n   rows, columns, mines[, seed]\n\
+                                                                  start a new game with the given board dimensions and mines.\n\
+                                      - n~  rows, columns, mines  start a new game on a torus, whose edges wrap around.\n\
+                                      - n   beginner|intermediate|expert\n\
+                                                                  start a new game on a 9x9, 16x16 or 16x30 board with 10, 40 or 99 mines.\n\
+                                      - n!  rows, columns, mines  start a new game on a board that can be solved without guessing.\n\
                                       - x   row, col              explore the cell at (row, col).\n\
-                                      - f/> row, col              flag the cell at (row, col).\n\
+                                      - x   row, col; row, col... explore several cells at once.\n\
+                                      - c   row, col              explore the unflagged neighbors of (row, col) if its\n\
+                                                                  number matches the flags around it.\n\
+                                      - H                         explore a cell that is provably safe, if any.\n\
+                                      - f/> row, col              flag the cell at (row, col), mark it with '?', or clear the mark.\n\
+                                      - R   row, col              use a radar on the 3x3 area centered at (row, col).\n\
+                                      - D   row, col              defuse the mine (if any) at (row, col).\n\
+                                      - e   rows, cols            add rows and columns, with mines at the same density, to the board.\n\
+                                      - t   r|h|v                 rotate the board clockwise, or mirror it horizontally or vertically.\n\
+                                      - r                         restart the current board, with the same mines.\n\
+                                      - u                         undo the last exploration, chord, hint or flag.\n\
+                                      - U                         redo the last move undone.\n\
+                                      - o   [option[=value]]      list the options, or set one of them.\n\
+                                      - b                         show a compact overview of the explored cells.\n\
+                                      - p                         print the board (when commands are piped in).\n\
+                                      - a                         list the achievements.\n\
+                                      - P                         show where the time went (profiling builds only).\n\
+                                      - d                         dump the state of the board (debug builds only).\n\
                                       - h                         print this message.\n\
                                       - q                         quit the game.\n\n\
+                                      The same seed always gives the same board.\n\
                                       Arguments to the `n' and `x' command are optional.\n\
-                                      An appropriate value will be chosen at random for each missing argument.\n");
+                                      An appropriate value will be chosen at random for each missing argument.\n\n\
+                                      Options:\n\n\
+                                      - moves=<n>                 clear the board within n moves (explorations and flags).\n\
+                                      - score=on                  compute a score when the board is cleared.\n\
+                                      - chord=on                  exploring a clear number again explores its unflagged neighbors.\n\
+                                      - treasures=<n>             hide n treasures ('$') in every new board.\n\
+                                      - powerups=<n>              hide n radar and defuse power-ups in every new board.\n\
+                                      - penalty=<s>               add s seconds to the final time for every wrong flag placed.\n\
+                                      - blitz=<s>                 in the next new game, clear as many boards as you can in s seconds.\n\
+                                      - survival=<s>              in the next new game, explore a quota of cells every s seconds\n\
+                                                                  before a new wave of mines is placed.\n\
+                                      - countdown=<s>             in the next new game, clear the board within s seconds.\n\
+                                      - sudden-time=<s>           place a new mine on the board every s seconds.\n\
+                                      - sudden-moves=<m>          place a new mine on the board every m moves.\n\
+                                      - status=<path>             write the status of the game to a file after every command.\n\
+                                      - labels=numbers|columns|rows|chess\n\
+                                                                  label columns and/or rows with letters, or as on a chess board.\n\
+                                      - bell=on                   ring the terminal bell when the game is over.\n\
+                                      - banner=on                 print a banner when the game is over.\n\
+                                      - flags=strict|unlimited    whether there may be more flags than mines.\n\
+                                      - symmetry=h|v|rot180       lay out the mines of new boards symmetrically.\n\
+                                      - radius=<r>                numbers of new boards count the mines up to r cells away.\n\
+                                      - orthogonal=on|off         numbers of new boards count only the four orthogonal neighbors.\n\
+                                      - liar=on|off               numbers of new boards are randomly off by one.\n\
+                                      - relocate=on|off           every wrong flag moves a mine to a random unexplored cell.\n\
+                                      - lives=<n>                 in new boards, n mines can be hit before the game is lost.\n\
+                                      - opening=on|off            the first cell explored in new boards has no mined neighbors.\n\n\
+                                      Set an option to 'off' (or give no value) to disable it.\n");
                             continue;
                         },
 
@@ -242,6 +1281,7 @@ fn main() {
                                 continue;
                             }
 
+                            info!("quit");
                             println!("Goodbye!");
                             break;
                         },
@@ -250,13 +1290,294 @@ fn main() {
                             println!("{prefix} Unknown commmand '{cmd}'.\n");
                         },
                     }
+
+                    if let Some(before) = before.filter(|_| counters.moves > moves_before) {
+                        board.record_move(before);
+                    }
+
+                    if explore {
+
+                        let exploration = profiling::measure("exploration", || board.explore());
+
+                        for finding in exploration.findings {
+                            match finding {
+                                Finding::Treasure => {
+                                    counters.treasures += 1;
+                                    board.forget_moves();
+                                    println!("{prefix} You found a treasure!\n");
+                                },
+                                Finding::PowerUp(power_up) => {
+                                    match power_up {
+                                        PowerUp::Radar => counters.radars += 1,
+                                        PowerUp::Defuse => counters.defusers += 1,
+                                    }
+                                    board.forget_moves();
+                                    println!("{prefix} You found a {power_up} power-up!\n");
+                                },
+                                Finding::Exploded(lives) => {
+                                    println!("{prefix} You hit a mine! Lives left: {lives}.\n");
+                                },
+                            }
+                        }
+
+                        match exploration.result {
+                            ExploreResult::Ok => {},
+                            ExploreResult::BoardClear => {
+
+                                // Roll to the next board of the blitz run, if any.
+                                if let Some(blitz) = blitz.as_mut() {
+                                    match blitz.next_board() {
+                                        Ok(mut new_board) => {
+                                            println!("{prefix} Board cleared! Rolling to the next one...\n");
+                                            prepare_board(&mut new_board, &options);
+                                            board = new_board;
+                                            counters.reset();
+                                            continue 'main;
+                                        },
+                                        Err(_) => {
+                                            println!("{prefix} Could not create the next board of the blitz run.\n");
+                                            break 'main;
+                                        }
+                                    }
+                                }
+
+                                println!("{prefix} Congratulations! All mines have \
+                                          been found!\n\n\
+                                          {board}\n\
+                                          Incorrect flags: {wrong_flags}\n",
+                                         wrong_flags = board.get_wrong_flag_count());
+
+                                // Wrong flags add a time penalty to the final time, if enabled.
+                                let mut seconds = counters.get_seconds();
+
+                                if let Some(penalty) = options.penalty {
+                                    let wrong_flags = board.get_wrong_flag_count();
+                                    let added = score::time_penalty(penalty, wrong_flags);
+                                    seconds = seconds.saturating_add(added);
+                                    println!("{prefix} Time penalty: {wrong_flags} wrong flags, +{added}s.\n\
+                                              {prefix} Final time: {}\n",
+                                             format_time(seconds));
+                                }
+
+                                if options.score {
+                                    let score = score::compute(&ScoreInput {
+                                        rows: board.get_rows(),
+                                        cols: board.get_cols(),
+                                        mines: board.get_mine_count(),
+                                        seconds,
+                                        moves: counters.moves,
+                                        wrong_flags: board.get_wrong_flag_count(),
+                                        treasures: counters.treasures,
+                                    });
+                                    println!("{prefix} Your score: {score}\n");
+                                }
+
+                                let summary = GameSummary {
+                                    rows: board.get_rows(),
+                                    cols: board.get_cols(),
+                                    mines: board.get_mine_count(),
+                                    seconds,
+                                    used_flags: counters.used_flags,
+                                };
+
+                                // Liar boards follow different rules, so they do not unlock achievements.
+                                let unlocked = match board.is_liar() {
+                                    true => Ok(Vec::new()),
+                                    false => achievements.evaluate(&summary),
+                                };
+
+                                match unlocked {
+                                    Ok(unlocked) => for achievement in unlocked {
+                                        println!("{prefix} Achievement unlocked: {name} \
+                                                  ({description}).",
+                                                 name = achievement.name,
+                                                 description = achievement.description);
+                                    },
+                                    Err(error) => {
+                                        println!("{prefix} Could not save your achievements: {error}");
+                                    }
+                                }
+                                if let Some(survival) = &survival {
+                                    println!("{prefix} You survived {} waves.\n", survival.get_waves());
+                                }
+                                info!("game won in {seconds}s and {} moves", counters.moves);
+                                outcome = GameState::Won;
+                                export_status(&options, &board, &counters, outcome);
+                                announce_end(&options, interactive, "YOU WON!");
+                                break 'main;
+                            },
+                            ExploreResult::Mined => {
+                                println!("{prefix} The cell is mined!\n\n\
+                                          {board}\n\
+                                          Incorrect flags: {wrong_flags}\n\
+                                          Game over!\n",
+                                         wrong_flags = board.get_wrong_flag_count());
+                                if let Some(survival) = &survival {
+                                    println!("{prefix} You survived {} waves.\n", survival.get_waves());
+                                }
+                                if let Some(blitz) = &blitz {
+                                    println!("{prefix} You cleared {} boards.\n", blitz.get_cleared());
+                                }
+                                info!("game lost after {} moves", counters.moves);
+                                outcome = GameState::Lost;
+                                export_status(&options, &board, &counters, outcome);
+                                announce_end(&options, interactive, "GAME OVER");
+                                if interactive && blitz.is_none() && ask_retry(prefix)? {
+                                    restart = true;
+                                    continue 'main;
+                                }
+                                break 'main;
+                            },
+                        }
+                    }
+
+                    // The game is lost if the board is not clear once the move limit is reached.
+                    if matches!(cmd, 'x' | 'c' | 'H' | 'D' | 'f' | '>') &&
+                        options.move_limit.is_some_and(|limit| counters.moves >= limit) {
+                        board.reveal_mines();
+                        println!("{prefix} Out of moves!\n\n\
+                                  {board}\n\
+                                  Incorrect flags: {wrong_flags}\n\
+                                  Game over!\n",
+                                 wrong_flags = board.get_wrong_flag_count());
+                        info!("game lost: out of moves");
+                        outcome = GameState::Lost;
+                        export_status(&options, &board, &counters, outcome);
+                        announce_end(&options, interactive, "GAME OVER");
+                        if interactive && blitz.is_none() && ask_retry(prefix)? {
+                            restart = true;
+                            continue 'main;
+                        }
+                        break 'main;
+                    }
                 }
             },
 
-            Err(_) => {
-                println!("{prefix} Error while reading input. Quitting the game...");
-                break;
-            }
+            Err(error) => return Err(error.into()),
         }
     }
+
+    if interactive {
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Without a terminal, the final board is always printed, and the exit status tells the outcome.
+    if let GameState::Playing = outcome {
+        println!("{board}\n{prefix} The game is not over.\n");
+    }
+
+    Ok(ExitCode::from(exit_code(outcome)))
+}
+
+// Exit status of a game run without a terminal: 0 if it was won (or the blitz run is over), 1 if
+// it was lost (or its countdown ran out), and 3 if it is not over. Errors use 2 and 4.
+fn exit_code(outcome: GameState) -> u8 {
+    match outcome {
+        GameState::Won | GameState::Over => 0,
+        GameState::Lost | GameState::TimeUp => 1,
+        GameState::Playing => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_describe_what_went_wrong() {
+        let error: Error = BoardError::TooManyMines.into();
+        assert_eq!(error.to_string(), "could not create the board: too many mines for such a small board");
+
+        let error: Error = io::Error::new(io::ErrorKind::BrokenPipe, "broken pipe").into();
+        assert_eq!(error.to_string(), "input/output error: broken pipe");
+    }
+
+    fn parse(args: &[&str]) -> Result<CommandLine, String> {
+        parse_command_line(args.iter().map(|arg| arg.to_string())).map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn log_level_is_read_from_the_command_line() {
+        assert_eq!(parse(&[]).unwrap().log_level, LevelFilter::Off);
+        assert_eq!(parse(&["--log-level", "debug"]).unwrap().log_level, LevelFilter::Debug);
+        assert_eq!(parse(&["--log-level=trace"]).unwrap().log_level, LevelFilter::Trace);
+
+        assert_eq!(parse(&["--log-level"]).err().unwrap(), "missing log level");
+        assert_eq!(parse(&["--log-level=loud"]).err().unwrap(), "invalid log level 'loud'");
+        assert_eq!(parse(&["--verbose"]).err().unwrap(), "unknown argument '--verbose'");
+    }
+
+    #[test]
+    fn paranoid_mode_is_set_from_the_command_line() {
+        assert!(!parse(&["--log-level=info"]).unwrap().paranoid);
+
+        let command_line = parse(&["--paranoid", "--log-level=info"]).unwrap();
+        assert!(command_line.paranoid);
+        assert_eq!(command_line.log_level, LevelFilter::Info);
+    }
+
+    #[test]
+    fn presets_are_chosen_by_number() {
+        assert_eq!(preset_choice("1"), Some(&("beginner", 9, 9, 10)));
+        assert_eq!(preset_choice("3"), Some(&("expert", 16, 30, 99)));
+        assert_eq!(preset_choice("0"), None);
+        assert_eq!(preset_choice("4"), None);
+        assert_eq!(preset_choice("expert"), None);
+    }
+
+    #[test]
+    fn coordinates_are_read_as_labeled() {
+        assert_eq!(parse_coords("3,5", &LabelScheme::Numbers, 8, None), Ok((3, 5)));
+        assert_eq!(parse_coords("3,e", &LabelScheme::LetterColumns, 8, None), Ok((3, 5)));
+        assert_eq!(parse_coords("e3", &LabelScheme::Chess, 8, None), Ok((6, 5)));
+        assert_eq!(parse_coords(",5", &LabelScheme::Numbers, 8, Some((2, 2))), Ok((2, 5)));
+        assert_eq!(parse_coords("", &LabelScheme::Chess, 8, Some((2, 2))), Ok((2, 2)));
+
+        assert_eq!(parse_coords("3", &LabelScheme::Numbers, 8, None),
+                   Err(String::from("invalid number of arguments (expected two)")));
+        assert_eq!(parse_coords("3,5,1", &LabelScheme::Numbers, 8, Some((2, 2))),
+                   Err(String::from("too many arguments, expected two at most: `[row]', `[colum]'")));
+        assert_eq!(parse_coords("3,0", &LabelScheme::Numbers, 8, None),
+                   Err(String::from("'0' is not a valid coordinate")));
+        assert_eq!(parse_coords("3,5", &LabelScheme::LetterColumns, 8, None),
+                   Err(String::from("'3,5' is not a valid coordinate")));
+    }
+
+    #[test]
+    fn exit_codes_tell_the_outcome() {
+        assert_eq!(exit_code(GameState::Won), 0);
+        assert_eq!(exit_code(GameState::Over), 0);
+        assert_eq!(exit_code(GameState::Lost), 1);
+        assert_eq!(exit_code(GameState::TimeUp), 1);
+        assert_eq!(exit_code(GameState::Playing), 3);
+
+        assert_eq!(Error::Usage(String::from("missing log level")).exit_code(), 2);
+        assert_eq!(Error::Board(BoardError::TooManyMines).exit_code(), 2);
+        assert_eq!(Error::Inconsistent(Vec::new()).exit_code(), 4);
+    }
+
+    #[test]
+    fn boards_without_guessing_rule_out_liars_and_treasures() {
+        let mut options = Options::default();
+        assert_eq!(no_guess_conflict(&options), None);
+
+        options.treasures = Some(3);
+        assert_eq!(no_guess_conflict(&options), Some("cannot hide treasures"));
+
+        options.liar = true;
+        assert_eq!(no_guess_conflict(&options), Some("cannot be liar boards"));
+    }
+
+    #[test]
+    fn end_notices_follow_the_options() {
+        let mut options = Options::default();
+        assert_eq!(end_notice(&options, "YOU WON!"), "");
+
+        options.bell = true;
+        assert_eq!(end_notice(&options, "YOU WON!"), "\x07");
+
+        options.banner = true;
+        assert_eq!(end_notice(&options, "YOU WON!"),
+                   "\x07****************\n*   YOU WON!   *\n****************\n\n");
+    }
 }
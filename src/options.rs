@@ -0,0 +1,277 @@
+//
+
+use std::{fmt, str::FromStr};
+use crate::game::{Adjacency, FlagPolicy, LabelScheme, Symmetry};
+
+pub enum OptionError<'a> {
+    UnknownOption(&'a str),
+    InvalidValue(&'a str),
+}
+
+// Game options that can be changed at the prompt with the 'o' command.
+pub struct Options {
+    // Maximum number of moves (explorations and flags) allowed to clear the board.
+    pub move_limit: Option<usize>,
+
+    // Whether a score is computed when the board is cleared.
+    pub score: bool,
+
+    // Whether exploring a clear cell again explores its unflagged neighbors.
+    pub chord: bool,
+
+    // Number of treasures hidden in each new board.
+    pub treasures: Option<usize>,
+
+    // Number of power-ups hidden in each new board.
+    pub power_ups: Option<usize>,
+
+    // Seconds added to the final time for every wrong flag.
+    pub penalty: Option<u64>,
+
+    // Length in seconds of the blitz run started with each new game.
+    pub blitz: Option<u64>,
+
+    // Length in seconds of the waves of the survival run started with each new game.
+    pub survival: Option<u64>,
+
+    // Seconds given to clear each new game.
+    pub countdown: Option<u64>,
+
+    // Seconds and moves after which a new mine is placed on the board.
+    pub sudden_time: Option<u64>,
+    pub sudden_moves: Option<usize>,
+
+    // File where the status of the game is written after every command, for status bars.
+    pub status: Option<String>,
+
+    // How rows and columns are labeled, on the board and in commands.
+    pub label_scheme: LabelScheme,
+
+    // Whether to ring the terminal bell and print a banner when the game is over.
+    pub bell: bool,
+    pub banner: bool,
+
+    // Whether more cells than mines may be flagged.
+    pub flag_policy: FlagPolicy,
+
+    // Symmetry of the mine layout of new boards, if any.
+    pub symmetry: Option<Symmetry>,
+
+    // Which cells count as neighbors in new boards.
+    pub adjacency: Adjacency,
+
+    // Whether the numbers of new boards are off by one.
+    pub liar: bool,
+
+    // Whether every flag placed on a cell that is not mined moves a mine elsewhere.
+    pub relocate: bool,
+
+    // Number of mines that can be hit in each new board before the game is lost.
+    pub lives: Option<usize>,
+
+    // Whether the first cell explored in new boards has no mined neighbors.
+    pub opening: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            move_limit: None,
+            score: false,
+            chord: false,
+            treasures: None,
+            power_ups: None,
+            penalty: None,
+            blitz: None,
+            survival: None,
+            countdown: None,
+            sudden_time: None,
+            sudden_moves: None,
+            status: None,
+            label_scheme: LabelScheme::Numbers,
+            bell: false,
+            banner: false,
+            flag_policy: FlagPolicy::Unlimited,
+            symmetry: None,
+            adjacency: Adjacency::Radius(1),
+            liar: false,
+            relocate: false,
+            lives: None,
+            opening: false,
+        }
+    }
+}
+
+impl fmt::Display for Options {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "- moves = {}", fmt_limit(self.move_limit))?;
+        writeln!(f, "- score = {}", fmt_switch(self.score))?;
+        writeln!(f, "- chord = {}", fmt_switch(self.chord))?;
+        writeln!(f, "- treasures = {}", fmt_limit(self.treasures))?;
+        writeln!(f, "- powerups = {}", fmt_limit(self.power_ups))?;
+        writeln!(f, "- penalty = {}", fmt_limit(self.penalty))?;
+        writeln!(f, "- blitz = {}", fmt_limit(self.blitz))?;
+        writeln!(f, "- survival = {}", fmt_limit(self.survival))?;
+        writeln!(f, "- countdown = {}", fmt_limit(self.countdown))?;
+        writeln!(f, "- sudden-time = {}", fmt_limit(self.sudden_time))?;
+        writeln!(f, "- sudden-moves = {}", fmt_limit(self.sudden_moves))?;
+        writeln!(f, "- status = {}", self.status.as_deref().unwrap_or("off"))?;
+        writeln!(f, "- labels = {}", match self.label_scheme {
+            LabelScheme::Numbers => "numbers",
+            LabelScheme::LetterColumns => "columns",
+            LabelScheme::LetterRows => "rows",
+            LabelScheme::Chess => "chess",
+        })?;
+        writeln!(f, "- bell = {}", fmt_switch(self.bell))?;
+        writeln!(f, "- banner = {}", fmt_switch(self.banner))?;
+        writeln!(f, "- flags = {}", match self.flag_policy {
+            FlagPolicy::Unlimited => "unlimited",
+            FlagPolicy::Strict => "strict",
+        })?;
+        writeln!(f, "- symmetry = {}", match self.symmetry {
+            Some(Symmetry::Horizontal) => "h",
+            Some(Symmetry::Vertical) => "v",
+            Some(Symmetry::Rot180) => "rot180",
+            None => "off",
+        })?;
+        writeln!(f, "- radius = {}", match self.adjacency {
+            Adjacency::Radius(radius) => radius.to_string(),
+            Adjacency::Orthogonal => String::from("off"),
+        })?;
+        writeln!(f, "- orthogonal = {}", fmt_switch(self.adjacency == Adjacency::Orthogonal))?;
+        writeln!(f, "- liar = {}", fmt_switch(self.liar))?;
+        writeln!(f, "- relocate = {}", fmt_switch(self.relocate))?;
+        writeln!(f, "- lives = {}", fmt_limit(self.lives))?;
+        writeln!(f, "- opening = {}", fmt_switch(self.opening))
+    }
+}
+
+impl Options {
+
+    // Set the option `name' from the string `value'. A missing value or "off" disables the option
+    // (or restores its default).
+    pub fn set<'a>(&mut self, name: &'a str, value: Option<&'a str>) -> Result<(), OptionError<'a>> {
+
+        match name {
+            "moves" => self.move_limit = parse_limit(value)?,
+            "score" => self.score = parse_switch(value)?,
+            "chord" => self.chord = parse_switch(value)?,
+            "treasures" => self.treasures = parse_limit(value)?,
+            "powerups" => self.power_ups = parse_limit(value)?,
+            "penalty" => self.penalty = parse_limit(value)?,
+            "blitz" => self.blitz = parse_limit(value)?,
+            "survival" => self.survival = parse_limit(value)?,
+            "countdown" => self.countdown = parse_limit(value)?,
+            "sudden-time" => self.sudden_time = parse_limit(value)?,
+            "sudden-moves" => self.sudden_moves = parse_limit(value)?,
+            "status" => self.status = value.filter(|&path| path != "off").map(String::from),
+            "labels" => self.label_scheme = match value {
+                None | Some("numbers") => LabelScheme::Numbers,
+                Some("columns") => LabelScheme::LetterColumns,
+                Some("rows") => LabelScheme::LetterRows,
+                Some("chess") => LabelScheme::Chess,
+                Some(slice) => return Err(OptionError::InvalidValue(slice)),
+            },
+            "bell" => self.bell = parse_switch(value)?,
+            "banner" => self.banner = parse_switch(value)?,
+            "flags" => self.flag_policy = match value {
+                None | Some("unlimited") => FlagPolicy::Unlimited,
+                Some("strict") => FlagPolicy::Strict,
+                Some(slice) => return Err(OptionError::InvalidValue(slice)),
+            },
+            "radius" => self.adjacency = Adjacency::Radius(parse_limit(value)?.unwrap_or(1)),
+            "orthogonal" => self.adjacency = match parse_switch(value)? {
+                true => Adjacency::Orthogonal,
+                false => Adjacency::Radius(1),
+            },
+            "liar" => self.liar = parse_switch(value)?,
+            "relocate" => self.relocate = parse_switch(value)?,
+            "lives" => self.lives = parse_limit(value)?,
+            "opening" => self.opening = parse_switch(value)?,
+            "symmetry" => self.symmetry = match value {
+                None | Some("off") => None,
+                Some("h") => Some(Symmetry::Horizontal),
+                Some("v") => Some(Symmetry::Vertical),
+                Some("rot180") => Some(Symmetry::Rot180),
+                Some(slice) => return Err(OptionError::InvalidValue(slice)),
+            },
+            _ => return Err(OptionError::UnknownOption(name)),
+        }
+
+        Ok(())
+    }
+
+    // Adjacency rule for a new board of `rows' x `cols' cells. Radii are capped at the larger
+    // dimension of the board, as a larger radius reaches no more cells.
+    pub fn adjacency_for(&self, rows: usize, cols: usize) -> Adjacency {
+        match self.adjacency {
+            Adjacency::Radius(radius) => Adjacency::Radius(radius.min(rows.max(cols))),
+            Adjacency::Orthogonal => Adjacency::Orthogonal,
+        }
+    }
+}
+
+fn parse_limit<T: FromStr + Default + PartialOrd>(value: Option<&str>) -> Result<Option<T>, OptionError<'_>> {
+    match value {
+        None | Some("off") => Ok(None),
+        Some(slice) => match slice.parse::<T>() {
+            Ok(n) if n > T::default() => Ok(Some(n)),
+            _ => Err(OptionError::InvalidValue(slice)),
+        }
+    }
+}
+
+fn parse_switch(value: Option<&str>) -> Result<bool, OptionError<'_>> {
+    match value {
+        None | Some("off") => Ok(false),
+        Some("on") => Ok(true),
+        Some(slice) => Err(OptionError::InvalidValue(slice)),
+    }
+}
+
+fn fmt_limit<T: ToString>(limit: Option<T>) -> String {
+    match limit {
+        Some(n) => n.to_string(),
+        None => String::from("off"),
+    }
+}
+
+fn fmt_switch(switch: bool) -> &'static str {
+    if switch { "on" } else { "off" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_must_be_positive() {
+        assert!(matches!(parse_limit::<usize>(Some("12")), Ok(Some(12))));
+        assert!(matches!(parse_limit::<usize>(None), Ok(None)));
+        assert!(matches!(parse_limit::<usize>(Some("off")), Ok(None)));
+        assert!(matches!(parse_limit::<usize>(Some("0")), Err(OptionError::InvalidValue("0"))));
+        assert!(matches!(parse_limit::<usize>(Some("-3")), Err(OptionError::InvalidValue("-3"))));
+        assert!(matches!(parse_limit::<u64>(Some("ten")), Err(OptionError::InvalidValue("ten"))));
+    }
+
+    #[test]
+    fn move_limit_is_set_and_cleared() {
+        let mut options = Options::default();
+        assert!(options.set("moves", Some("30")).is_ok());
+        assert_eq!(options.move_limit, Some(30));
+        assert!(options.set("moves", None).is_ok());
+        assert_eq!(options.move_limit, None);
+
+        assert!(matches!(options.set("move", Some("30")), Err(OptionError::UnknownOption("move"))));
+    }
+
+    #[test]
+    fn radius_is_capped_by_the_board() {
+        let mut options = Options::default();
+        assert!(options.set("radius", Some("18446744073709551615")).is_ok());
+        assert!(options.adjacency_for(9, 30) == Adjacency::Radius(30));
+        assert!(options.set("radius", Some("2")).is_ok());
+        assert!(options.adjacency_for(9, 30) == Adjacency::Radius(2));
+        assert!(matches!(options.set("radius", Some("0")), Err(OptionError::InvalidValue("0"))));
+    }
+}
@@ -0,0 +1,75 @@
+//
+
+// Timing counters for the main stages of the game, collected only when rmines is built with the
+// `profiling' feature. Without it, measuring a stage just runs it.
+
+#[cfg(feature = "profiling")]
+use std::{cell::RefCell, fmt::Write, time::{Duration, Instant}};
+
+#[cfg(feature = "profiling")]
+thread_local! {
+    // Name, number of runs and total time of every stage measured so far, in order of first run.
+    static STAGES: RefCell<Vec<(&'static str, u32, Duration)>> = const { RefCell::new(Vec::new()) };
+}
+
+#[cfg(feature = "profiling")]
+pub fn measure<T>(stage: &'static str, run: impl FnOnce() -> T) -> T {
+
+    let start = Instant::now();
+    let result = run();
+    let elapsed = start.elapsed();
+
+    STAGES.with_borrow_mut(|stages| match stages.iter_mut().find(|(name, _, _)| *name == stage) {
+        Some((_, runs, total)) => {
+            *runs += 1;
+            *total += elapsed;
+        },
+        None => stages.push((stage, 1, elapsed)),
+    });
+
+    result
+}
+
+#[cfg(not(feature = "profiling"))]
+pub fn measure<T>(_stage: &'static str, run: impl FnOnce() -> T) -> T {
+    run()
+}
+
+// Summary of where the time went, one line per stage, or None if profiling is not available.
+#[cfg(feature = "profiling")]
+pub fn report() -> Option<String> {
+
+    STAGES.with_borrow(|stages| {
+        let mut report = String::new();
+        for (name, runs, total) in stages {
+            let _ = writeln!(report, "- {name:<12} {runs:>8} runs {:>12.3} ms total {:>10.3} ms per run",
+                             total.as_secs_f64() * 1e3, total.as_secs_f64() * 1e3 / *runs as f64);
+        }
+        Some(report)
+    })
+}
+
+#[cfg(not(feature = "profiling"))]
+pub fn report() -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measured_stages_run_and_are_reported() {
+        assert_eq!(measure("test", || 6 * 7), 42);
+        assert_eq!(measure("test", || "again"), "again");
+
+        let report = report();
+        if cfg!(feature = "profiling") {
+            let report = report.unwrap();
+            assert!(report.contains("- test"));
+            assert!(report.contains("2 runs"));
+        } else {
+            assert!(report.is_none());
+        }
+    }
+}
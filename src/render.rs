@@ -0,0 +1,140 @@
+//
+
+use crate::game::{Board, CellLabel, CellView};
+
+// Presentation of a board, built from what the player may see of each cell (see Board::cells()),
+// so that the game itself does not depend on how it is shown.
+pub trait Renderer {
+    fn render(&self, board: &Board) -> String;
+}
+
+// The board as a grid of characters, with rows and columns labeled as typed in commands. This is
+// how boards are printed (see Board's Display implementation).
+pub struct AsciiRenderer;
+
+impl AsciiRenderer {
+
+    // Symbol shown for a cell.
+    fn symbol(view: CellView) -> String {
+        match view {
+            CellView::Hidden => String::from("."),
+            CellView::Flagged => String::from(">"),
+            CellView::Questioned => String::from("?"),
+            CellView::Exploded | CellView::Mine => String::from("*"),
+            CellView::WrongFlag => String::from("X"),
+            CellView::Revealed(CellLabel::Clear) => String::from(" "),
+            CellView::Revealed(CellLabel::Treasure) => String::from("$"),
+            CellView::Revealed(CellLabel::MinedNeighbors(mine_count)) => mine_count.to_string(),
+        }
+    }
+}
+
+impl Renderer for AsciiRenderer {
+
+    fn render(&self, board: &Board) -> String {
+
+        let (rows, cols) = (board.get_rows(), board.get_cols());
+        let scheme = board.get_label_scheme();
+
+        // Labels are never longer than the numbers of the last row and column.
+        let row_label_width = rows.to_string().len() + 1;
+        let col_label_width = cols.to_string().len() + 2;
+
+        let mut board_string: String =
+            String::with_capacity(((col_label_width + 2) * cols + (row_label_width + 2)) * (rows + 1));
+
+        // Header including column labels and separators.
+        board_string.push_str(format!("{:>width$}|", scheme.col_label(1),
+                                      width = (row_label_width + 1) +
+                                      col_label_width).as_str());
+
+        for col in 2..(cols + 1) {
+            board_string.push_str(format!("{:>col_label_width$}|", scheme.col_label(col)).as_str());
+        }
+
+        // Row labels, then cells right-aligned so that the columns stay aligned.
+        for ((row, col), view) in board.cells() {
+            if col == 1 {
+                board_string.push('\n');
+                board_string.push_str(format!("{:>row_label_width$}|", scheme.row_label(row, rows)).as_str());
+            }
+            board_string.push_str(format!("{:>width$}", AsciiRenderer::symbol(view),
+                                          width = col_label_width + 1).as_str());
+        }
+
+        board_string.push('\n');
+        board_string
+    }
+}
+
+// Compact overview of the board in braille characters, each covering 4 rows and 2 columns of
+// cells. A raised dot stands for an explored cell.
+pub struct BrailleRenderer;
+
+impl Renderer for BrailleRenderer {
+
+    fn render(&self, board: &Board) -> String {
+
+        // Bit of the braille pattern for each (row, column) of the 4x2 block.
+        const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+        let (rows, cols) = (board.get_rows(), board.get_cols());
+        let mut patterns = vec![vec![0; cols.div_ceil(2)]; rows.div_ceil(4)];
+
+        for ((row, col), view) in board.cells() {
+            if let CellView::Revealed(_) = view {
+                patterns[(row - 1) / 4][(col - 1) / 2] |= DOTS[(row - 1) % 4][(col - 1) % 2];
+            }
+        }
+
+        let mut overview = String::with_capacity((rows / 4 + 1) * (cols / 2 + 2) * 3);
+
+        for block_row in patterns {
+            for pattern in block_row {
+                overview.push(char::from_u32(0x2800 + pattern).unwrap_or(' '));
+            }
+            overview.push('\n');
+        }
+
+        overview
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::FlagPolicy;
+
+    #[test]
+    fn ascii_renderer_aligns_labels_and_cells() {
+        let mut board = Board::new(2, 3, 0).unwrap();
+        board.toggle_flag_at((2, 3), FlagPolicy::Unlimited);
+        assert_eq!(AsciiRenderer.render(&board), "     1|  2|  3|\n 1|   .   .   .\n 2|   .   .   >\n");
+
+        board.cache((1, 1));
+        board.explore();
+        assert_eq!(BrailleRenderer.render(&board), "\u{281b}\u{2803}\n");
+    }
+
+    #[test]
+    fn wide_labels_keep_the_columns_aligned() {
+        let board = Board::new(100, 12, 0).unwrap();
+        let rendered = AsciiRenderer.render(&board);
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        assert_eq!(lines.len(), 101);
+        assert!(lines[0].ends_with(" 11|  12|"));
+        assert!(lines[100].starts_with(" 100|"));
+        assert!(lines[1..].iter().all(|line| line.len() == lines[1].len()));
+    }
+
+    #[test]
+    fn braille_blocks_cover_4_rows_and_2_columns() {
+        let mut board = Board::new(5, 3, 0).unwrap();
+        assert_eq!(BrailleRenderer.render(&board), "\u{2800}\u{2800}\n\u{2800}\u{2800}\n");
+
+        board.cache((1, 1));
+        board.explore();
+        assert_eq!(BrailleRenderer.render(&board), "\u{28ff}\u{2847}\n\u{2809}\u{2801}\n");
+    }
+}
@@ -0,0 +1,82 @@
+//
+
+/* The score of a cleared board is computed as
+ *
+ *     score = 10 * area * (1 + 4 * density) * speed * efficiency
+ *             - 50 * wrong_flags + 100 * treasures
+ *
+ * where
+ *
+ *     density    = mines / area,
+ *     speed      = 300 / (300 + seconds),
+ *     efficiency = area / (area + moves),
+ *
+ * and the result is rounded to the nearest integer and never negative. Bigger
+ * and denser boards are worth more, while slow games and games taking many moves
+ * are worth less. Every flag placed on a cell that is not mined costs 50 points,
+ * and every treasure found is worth 100 points.
+ */
+
+const DENSITY_WEIGHT: f64 = 4.0;
+const SPEED_SCALE: f64 = 300.0;
+const WRONG_FLAG_PENALTY: f64 = 50.0;
+const TREASURE_BONUS: f64 = 100.0;
+
+pub struct ScoreInput {
+    pub rows: usize,
+    pub cols: usize,
+    pub mines: usize,
+    pub seconds: u64,
+    pub moves: usize,
+    pub wrong_flags: usize,
+    pub treasures: usize,
+}
+
+pub fn compute(input: &ScoreInput) -> u64 {
+
+    let area = (input.rows * input.cols) as f64;
+    let density = input.mines as f64 / area;
+    let speed = SPEED_SCALE / (SPEED_SCALE + input.seconds as f64);
+    let efficiency = area / (area + input.moves as f64);
+
+    let score = 10.0 * area * (1.0 + DENSITY_WEIGHT * density) * speed * efficiency
+        - WRONG_FLAG_PENALTY * input.wrong_flags as f64
+        + TREASURE_BONUS * input.treasures as f64;
+
+    score.max(0.0).round() as u64
+}
+
+// Seconds added to the final time for `wrong_flags' wrong flags, at `seconds' seconds each.
+pub fn time_penalty(seconds: u64, wrong_flags: usize) -> u64 {
+    seconds.saturating_mul(wrong_flags as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(mines: usize, seconds: u64, moves: usize, wrong_flags: usize) -> ScoreInput {
+        ScoreInput { rows: 10, cols: 10, mines, seconds, moves, wrong_flags, treasures: 0 }
+    }
+
+    #[test]
+    fn score_follows_the_formula() {
+        assert_eq!(compute(&input(0, 0, 0, 0)), 1000);
+        assert_eq!(compute(&input(20, 0, 0, 0)), 1800);
+        assert_eq!(compute(&input(20, 300, 0, 0)), 900);
+        assert_eq!(compute(&input(20, 300, 100, 0)), 450);
+    }
+
+    #[test]
+    fn wrong_flags_cost_points_down_to_zero() {
+        assert_eq!(compute(&input(20, 300, 100, 2)), 350);
+        assert_eq!(compute(&input(20, 300, 100, 100)), 0);
+    }
+
+    #[test]
+    fn time_penalty_grows_with_wrong_flags() {
+        assert_eq!(time_penalty(15, 0), 0);
+        assert_eq!(time_penalty(15, 3), 45);
+        assert_eq!(time_penalty(u64::MAX, 2), u64::MAX);
+    }
+}
@@ -0,0 +1,69 @@
+//
+
+use std::{fs, io, path::Path};
+use crate::game::Board;
+
+// State of the game reported in the status file.
+#[derive(Clone, Copy)]
+pub enum GameState {
+    Playing,
+    Won,
+    Lost,
+    // A timed run (blitz) ended when its time was up.
+    Over,
+    // The board was not cleared before the countdown ran out.
+    TimeUp,
+}
+
+impl GameState {
+    fn as_str(self) -> &'static str {
+        match self {
+            GameState::Playing => "playing",
+            GameState::Won => "won",
+            GameState::Lost => "lost",
+            GameState::Over => "over",
+            GameState::TimeUp => "time-up",
+        }
+    }
+}
+
+/* Write the status of the game to `path', as a single JSON object such as
+ *
+ *     {"state":"playing","mines_left":12,"seconds":95,"moves":31}
+ *
+ * where `mines_left' is the number of mines minus the number of flags (negative when there are
+ * more flags than mines). The file is written to a temporary file first and then renamed, so
+ * that status bars reading it never see it half written.
+ */
+pub fn write(path: &str, board: &Board, seconds: u64, moves: usize, state: GameState) -> io::Result<()> {
+
+    let mines_left = board.get_mine_count() as i64 - board.get_flagged_count() as i64;
+    let contents = format!("{{\"state\":\"{}\",\"mines_left\":{mines_left},\"seconds\":{seconds},\
+                            \"moves\":{moves}}}\n", state.as_str());
+
+    let temporary = format!("{path}.tmp");
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, Path::new(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::FlagPolicy;
+
+    #[test]
+    fn status_is_written_as_one_line_of_json() {
+        let path = std::env::temp_dir().join(format!("rmines-status-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let mut board = Board::new(3, 3, 1).unwrap();
+        board.toggle_flag_at((1, 1), FlagPolicy::Unlimited);
+        board.toggle_flag_at((1, 2), FlagPolicy::Unlimited);
+        write(path, &board, 95, 2, GameState::TimeUp).unwrap();
+
+        let contents = fs::read_to_string(path).unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(contents, "{\"state\":\"time-up\",\"mines_left\":-1,\"seconds\":95,\"moves\":2}\n");
+        assert!(!Path::new(&format!("{path}.tmp")).exists());
+    }
+}
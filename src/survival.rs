@@ -0,0 +1,104 @@
+//
+
+use std::time::{Duration, SystemTime};
+use crate::game::Board;
+
+// Each wave asks for a tenth of the area to be explored, and adds a twentieth of it in mines.
+const QUOTA_SHARE: usize = 10;
+const MINES_SHARE: usize = 20;
+
+// A survival run: explore a quota of cells before each wave is over, after which new mines are
+// placed on the board. The run goes on until the player hits a mine or misses a quota.
+pub struct Survival {
+    wave_seconds: u64,
+    deadline: SystemTime,
+
+    // Number of cells to explore in the current wave, and number of explored cells when it began.
+    quota: usize,
+    explored_at_start: usize,
+
+    // Number of waves survived so far.
+    waves: usize,
+}
+
+impl Survival {
+
+    pub fn new(wave_seconds: u64, board: &Board) -> Self {
+        let mut survival = Survival {
+            wave_seconds,
+            deadline: SystemTime::now(),
+            quota: 0,
+            explored_at_start: 0,
+            waves: 0,
+        };
+        survival.start_wave(board);
+        survival
+    }
+
+    // The quota never exceeds the number of safe cells left, so that it can always be met.
+    fn start_wave(&mut self, board: &Board) {
+        let area = board.get_rows() * board.get_cols();
+        let safe_left = area - board.get_mine_count() - board.get_explored_count();
+
+        self.deadline = SystemTime::now() + Duration::from_secs(self.wave_seconds);
+        self.quota = (area / QUOTA_SHARE).max(1).min(safe_left);
+        self.explored_at_start = board.get_explored_count();
+    }
+
+    pub fn get_seconds_left(&self) -> u64 {
+        self.deadline
+            .duration_since(SystemTime::now())
+            .map_or(0, |duration| duration.as_secs())
+    }
+
+    // Number of cells still to explore to meet the quota of the current wave.
+    pub fn get_cells_left(&self, board: &Board) -> usize {
+        let explored = board.get_explored_count().saturating_sub(self.explored_at_start);
+        self.quota.saturating_sub(explored)
+    }
+
+    pub fn get_waves(&self) -> usize {
+        self.waves
+    }
+
+    pub fn is_wave_over(&self) -> bool {
+        SystemTime::now() >= self.deadline
+    }
+
+    // Count the current wave as survived, place the mines of the next one and return how many
+    // could be placed.
+    pub fn next_wave(&mut self, board: &mut Board) -> usize {
+        self.waves += 1;
+
+        let area = board.get_rows() * board.get_cols();
+        let added = (0..(area / MINES_SHARE).max(1))
+            .take_while(|_| board.add_mine().is_some())
+            .count();
+
+        self.start_wave(board);
+        added
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waves_ask_for_a_quota_and_add_mines() {
+        let mut board = Board::new_seeded(10, 10, 10, 42).unwrap();
+        let mut survival = Survival::new(60, &board);
+        assert_eq!(survival.get_cells_left(&board), 10);
+        assert!(!survival.is_wave_over());
+
+        board.cache((5, 5));
+        board.explore();
+        let explored = board.get_explored_count();
+        assert_eq!(survival.get_cells_left(&board), 10usize.saturating_sub(explored));
+
+        assert_eq!(survival.next_wave(&mut board), 5);
+        assert_eq!(survival.get_waves(), 1);
+        assert_eq!(board.get_mine_count(), 15);
+        assert_eq!(survival.get_cells_left(&board), 10.min(100 - 15 - explored));
+    }
+}